@@ -0,0 +1,88 @@
+use crate::config::{PlatformConfig, WokwiBoard};
+use anyhow::{Context, Result};
+use include_dir::{Dir, include_dir};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+static BOARDS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/boards");
+
+/// A development board: its chip, pin labels and the pins of its buses.
+///
+/// One file per board is bundled under `boards/`, named after the value of
+/// `espforge.board` (e.g. `xiao-esp32c3.toml`).
+#[derive(Debug, Deserialize)]
+pub struct BoardDefinition {
+    pub board: BoardInfo,
+    /// Silkscreen labels (`D0`, `SDA`, `LED_BUILTIN`, ...) to GPIO numbers
+    #[serde(default)]
+    pub pins: BTreeMap<String, u8>,
+    pub i2c: Option<I2cPins>,
+    pub spi: Option<SpiPins>,
+    pub uart: Option<UartPins>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BoardInfo {
+    pub title: String,
+    pub chip: PlatformConfig,
+    /// The Wokwi part simulating the board, if there is one
+    pub wokwi: Option<WokwiBoard>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct I2cPins {
+    pub sda: u8,
+    pub scl: u8,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpiPins {
+    pub sck: u8,
+    pub mosi: u8,
+    pub miso: u8,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UartPins {
+    pub tx: u8,
+    pub rx: u8,
+}
+
+/// Names of the bundled boards, sorted.
+pub fn board_names() -> Vec<String> {
+    let mut names: Vec<String> = BOARDS_DIR
+        .files()
+        .filter_map(|file| file.path().file_stem()?.to_str().map(str::to_string))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Loads the bundled definition of the board called `name`.
+pub fn load_board(name: &str) -> Result<BoardDefinition> {
+    let file_name = format!("{}.toml", name);
+    let file = BOARDS_DIR.get_file(&file_name).with_context(|| {
+        format!(
+            "Unknown board '{}'; available boards: {}",
+            name,
+            board_names().join(", ")
+        )
+    })?;
+
+    let content = file
+        .contents_utf8()
+        .with_context(|| format!("Board file {} is not valid UTF-8", file_name))?;
+    toml::from_str(content)
+        .with_context(|| format!("Failed to parse board definition {}", file_name))
+}
+
+impl BoardDefinition {
+    /// The GPIO number of the pin labelled `label`.
+    pub fn pin(&self, label: &str) -> Option<u8> {
+        self.pins.get(label).copied()
+    }
+
+    pub fn labels(&self) -> Vec<&str> {
+        self.pins.keys().map(String::as_str).collect()
+    }
+}
@@ -0,0 +1,204 @@
+use super::{CompileOptions, context, validation};
+use crate::config::{SourceMap, Span};
+use crate::nibblers::{Finding, Severity};
+use crate::resolver::ConfigPath;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Result of validating a configuration without generating a project.
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub file: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(skip)]
+    source_map: SourceMap,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    /// Nibbler (or pipeline stage) that produced the diagnostic
+    pub source: String,
+    #[serde(flatten)]
+    pub finding: Finding,
+    /// Position of `finding.path` in the file, when it could be located
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// The included fragment `span` is in, when it is not the checked file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span_file: Option<PathBuf>,
+}
+
+/// Runs every nibbler and, when they pass, a dry run of the resolver and the
+/// Ruchy compiler. Informational findings are dropped; only warnings and
+/// errors end up in the report.
+pub fn check(path: &Path, options: &CompileOptions) -> CheckReport {
+    let mut report = CheckReport {
+        file: path.to_path_buf(),
+        diagnostics: Vec::new(),
+        source_map: SourceMap::default(),
+    };
+
+    let config = match validation::load_config(path, options) {
+        Ok((config, source_map)) => {
+            report.source_map = source_map;
+            config
+        }
+        Err(e) => {
+            let undefined = validation::substitution_findings(&e);
+            if undefined.is_empty() {
+                report.push("Parser", Finding::error("config-invalid", "", format!("{:#}", e)));
+            } else {
+                report.source_map = SourceMap::read(path);
+                for finding in undefined {
+                    report.push("Substitutions", finding);
+                }
+            }
+            return report;
+        }
+    };
+
+    for result in validation::run_nibblers(&config) {
+        for finding in result.findings {
+            if finding.severity > Severity::Info {
+                report.push(&result.nibbler_name, finding);
+            }
+        }
+    }
+
+    // Resolving against a config the nibblers rejected only repeats their errors
+    if report.has_errors() {
+        return report;
+    }
+
+    let config_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let resolved = context::prepare_tera_context(&config).and_then(|mut tera_context| {
+        context::resolve_application_logic(&config, config_dir, &mut tera_context)
+    });
+    if let Err(e) = resolved {
+        let path = e
+            .downcast_ref::<ConfigPath>()
+            .map(|p| p.0.clone())
+            .unwrap_or_default();
+        report.push("Resolver", Finding::error("resolve-failed", path, format!("{:#}", e)));
+    }
+
+    report
+}
+
+impl CheckReport {
+    fn push(&mut self, source: &str, finding: Finding) {
+        let location = self.source_map.locate(&finding.path);
+        self.diagnostics.push(Diagnostic {
+            source: source.to_string(),
+            span: location.map(|l| l.span),
+            span_file: location.and_then(|l| l.file).map(Path::to_path_buf),
+            finding,
+        });
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.finding.severity == Severity::Error)
+    }
+
+    /// Renders the report as a SARIF 2.1.0 log for code-scanning tools.
+    pub fn to_sarif(&self) -> Value {
+        let mut rules = BTreeMap::new();
+        for diagnostic in &self.diagnostics {
+            rules.entry(diagnostic.finding.code).or_insert_with(|| {
+                json!({
+                    "id": diagnostic.finding.code,
+                    "properties": { "nibbler": diagnostic.source }
+                })
+            });
+        }
+
+        let uri = self.file.display().to_string();
+        let results: Vec<Value> = self
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let uri = d
+                    .span_file
+                    .as_ref()
+                    .map_or_else(|| uri.clone(), |file| file.display().to_string());
+                let mut location = json!({
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri }
+                    }
+                });
+                if let Some(span) = d.span {
+                    location["physicalLocation"]["region"] = json!({
+                        "startLine": span.line,
+                        "startColumn": span.column,
+                        "endColumn": span.column + span.length
+                    });
+                }
+                if !d.finding.path.is_empty() {
+                    location["logicalLocations"] =
+                        json!([{ "fullyQualifiedName": d.finding.path }]);
+                }
+
+                json!({
+                    "ruleId": d.finding.code,
+                    "level": match d.finding.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                        Severity::Info => "note",
+                    },
+                    "message": { "text": d.finding.message },
+                    "locations": [location]
+                })
+            })
+            .collect();
+
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "espforge",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules.into_values().collect::<Vec<_>>()
+                    }
+                },
+                "results": results
+            }]
+        })
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for d in &self.diagnostics {
+            write!(
+                f,
+                "{}",
+                validation::render_finding(&self.file, &self.source_map, &d.finding)
+            )?;
+            writeln!(f, "  = reported by {}\n", d.source)?;
+        }
+
+        let errors = self
+            .diagnostics
+            .iter()
+            .filter(|d| d.finding.severity == Severity::Error)
+            .count();
+        let warnings = self.diagnostics.len() - errors;
+        writeln!(
+            f,
+            "{}: {} error(s), {} warning(s)",
+            self.file.display(),
+            errors,
+            warnings
+        )
+    }
+}
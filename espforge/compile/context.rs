@@ -1,4 +1,4 @@
-use crate::config::{EspforgeConfig, EspforgeConfiguration};
+use crate::config::{EspforgeConfig, EspforgeConfiguration, GeneratorOption};
 use crate::resolver::{ruchy_bridge, ContextResolver};
 use anyhow::Result;
 use serde::Serialize;
@@ -9,6 +9,10 @@ struct EspforgeMeta<'a> {
     #[serde(flatten)]
     espforge: &'a EspforgeConfig,
     target: &'a str,
+    /// Wokwi part type of `wokwi_board`, used for the generated `diagram.json`
+    wokwi_part: Option<&'static str>,
+    /// Options the project skeleton was generated with (e.g. `defmt` or `log`)
+    generator_options: Vec<GeneratorOption>,
 }
 
 pub fn prepare_tera_context(config: &EspforgeConfiguration) -> Result<tera::Context> {
@@ -20,6 +24,8 @@ pub fn prepare_tera_context(config: &EspforgeConfiguration) -> Result<tera::Cont
     context.insert("espforge", &EspforgeMeta {
         espforge: &config.espforge,
         target: config.espforge.platform.target(),
+        wokwi_part: config.espforge.wokwi_board.map(|b| b.part_type()),
+        generator_options: config.espforge.generator_options(),
     });
     
     Ok(context)
@@ -30,7 +36,7 @@ pub fn resolve_application_logic(
     config_dir: &Path,
     context: &mut tera::Context,
 ) -> Result<()> {
-    let manifests = crate::generate::load_manifests()?;
+    let manifests = crate::generate::load_config_manifests(config)?;
     let mut resolver = ContextResolver::new();
     let mut render_ctx = resolver.resolve(config, &manifests)?;
 
@@ -1,48 +1,118 @@
-use anyhow::{Error, Result};
+use crate::config::GeneratorBackend;
+use crate::generate::{OutputReport, ProjectOutput};
+use crate::generate::cargo::SkeletonInfo;
+use anyhow::{Error, Result, bail};
+use log::info;
 use std::path::{Path, PathBuf};
 
+pub mod check;
 mod validation;
 mod context;
 mod scripting;
 mod template;
 mod postprocess;
+#[cfg(feature = "cli")]
+pub mod watch;
 
-pub fn compile<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+#[derive(Default, Clone)]
+pub struct CompileOptions {
+    /// Override the platform/chip declared in the configuration (e.g. esp32s3)
+    pub override_platform: Option<String>,
+    /// Override `espforge.generator.backend` from the configuration
+    pub backend: Option<GeneratorBackend>,
+    /// Overwrite generated files that were edited outside user regions
+    pub force: bool,
+    /// Only report what would change, with a diff, without writing anything
+    pub dry_run: bool,
+    /// Generate the project here instead of `espforge.output` / `./<name>`
+    pub out_dir: Option<PathBuf>,
+    /// Overlay this entry of `profiles:` on the configuration
+    pub profile: Option<String>,
+}
+
+/// Generates the project, or updates it in place when it already exists.
+pub fn compile<P: AsRef<Path>>(path: P, options: CompileOptions) -> Result<OutputReport, Error> {
     let config_path = path.as_ref();
     let config_dir = config_path.parent().unwrap_or_else(|| Path::new("."));
     
-    let config = validation::load_and_validate(config_path)?;
+    let (config, source_map) = validation::load_and_validate(config_path, &options)?;
 
     let project_name = config.get_name();
-    crate::generate::generate(
-        project_name,
-        &config.get_platform(),
-        config.espforge.enable_async,
-    )?;
+    let chip = config.get_platform();
+    let generator_options = config.espforge.generator_options();
+    let project_path = options
+        .out_dir
+        .clone()
+        .unwrap_or_else(|| config.espforge.output_dir(config_dir));
 
-    let project_path = PathBuf::from(project_name);
-    let src_path = project_path.join("src");
+    // The skeleton is only generated when the project is created, or again
+    // (with --force) when the chip or generator options it was made for change
+    let skeleton = SkeletonInfo::new(config.espforge.platform, &generator_options);
+    let cargo_path = project_path.join("Cargo.toml");
+    let regenerate = match std::fs::read_to_string(&cargo_path) {
+        Err(_) => true,
+        Ok(existing) => match SkeletonInfo::recorded(&existing) {
+            Some(recorded) if recorded != skeleton => {
+                if !options.force {
+                    bail!(
+                        "{} was generated for {}, but the configuration now asks for {}. \
+                         Re-run with --force to regenerate Cargo.toml, .cargo/config.toml and \
+                         rust-toolchain.toml (losing edits outside user regions), or compile \
+                         into another --out-dir.",
+                        project_path.display(),
+                        recorded,
+                        skeleton
+                    );
+                }
+                info!("Regenerating the project skeleton for {}", skeleton);
+                true
+            }
+            _ => false,
+        },
+    };
+    let mut output = ProjectOutput::new(&project_path);
+    if regenerate {
+        crate::generate::generate(
+            &mut output,
+            project_name,
+            config.espforge.platform,
+            &generator_options,
+            config.espforge.generator_backend(),
+        )?;
+    } else {
+        info!("Updating existing project in {}", project_path.display());
+    }
 
-    // Copy necessary infrastructure files
-    crate::generate::operations::copy_components(&src_path)?;
-    crate::generate::operations::copy_platform_files(&src_path)?;
-    crate::generate::operations::copy_globals_files(&src_path)?;
-    crate::generate::operations::copy_devices(&src_path)?;
+    // Copy only the infrastructure files the configuration uses
+    let manifests = crate::generate::load_config_manifests(&config)?;
+    let modules = crate::generate::required_modules(&config, &manifests);
+    crate::generate::operations::copy_components(&mut output, &modules, &manifests)?;
+    crate::generate::operations::copy_platform_files(&mut output, &modules)?;
+    crate::generate::operations::copy_globals_files(&mut output)?;
+    crate::generate::operations::copy_devices(&mut output, &modules, &manifests)?;
 
     let mut tera_context = context::prepare_tera_context(&config)?;
-    context::resolve_application_logic(&config, config_dir, &mut tera_context)?;    
-    template::apply_templates(&config, &project_path, &tera_context)?;
+    context::resolve_application_logic(&config, config_dir, &mut tera_context)
+        .inspect_err(|e| validation::report_located_error(config_path, &source_map, e))?;
+    template::apply_templates(&config, &mut output, &tera_context)?;
     
     // Update Cargo.toml (Merge dependencies)
     crate::generate::cargo::update_manifest(
-        &project_path,
+        &mut output,
         config_dir,
-        config.espforge.platform.target(), 
-        &tera_context
+        &chip,
+        &tera_context,
+        &modules,
+        &manifests,
+        &generator_options,
     )?;
+    crate::generate::cargo::record_metadata(&mut output, &skeleton, config.profile.as_deref())?;
 
-    postprocess::refine_project_files(&project_path, config_dir, &tera_context)?;
+    postprocess::refine_project_files(&mut output, config_dir, &tera_context)?;
 
-    Ok(())
+    if options.dry_run {
+        output.preview(options.force)
+    } else {
+        output.write(options.force)
+    }
 }
-
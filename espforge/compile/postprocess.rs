@@ -1,30 +1,35 @@
+use crate::generate::output::ProjectOutput;
 use anyhow::Result;
 use std::fs;
 use std::path::Path;
 
-pub fn refine_project_files(project_path: &Path, config_dir: &Path, context: &tera::Context) -> Result<()> {
+/// Templates next to the configuration, rendered into the project.
+pub(crate) const RENDERED_FILES: [(&str, &str); 1] = [("wokwi.toml.tera", "wokwi.toml")];
+/// Files next to the configuration, copied into the project as is.
+pub(crate) const STATIC_ASSETS: [&str; 4] = ["diagram.json", "wokwi.toml", "chip.wasm", "chip.json"];
+
+pub fn refine_project_files(output: &mut ProjectOutput, config_dir: &Path, context: &tera::Context) -> Result<()> {
     // Cargo dependencies are now handled in generate::cargo::update_manifest
-    apply_local_overrides(project_path, config_dir, context)?;
+    apply_local_overrides(output, config_dir, context)?;
     Ok(())
 }
 
-fn apply_local_overrides(project_path: &Path, config_dir: &Path, context: &tera::Context) -> Result<()> {
+fn apply_local_overrides(output: &mut ProjectOutput, config_dir: &Path, context: &tera::Context) -> Result<()> {
     // Rendered files
-    if let Some(rendered_pair) = [("wokwi.toml.tera", "wokwi.toml")]
+    if let Some(rendered_pair) = RENDERED_FILES
         .iter()
         .find(|(src, _)| config_dir.join(src).exists()) 
     {
         let content = fs::read_to_string(config_dir.join(rendered_pair.0))?;
         let rendered = tera::Tera::one_off(&content, context, true)?;
-        fs::write(project_path.join(rendered_pair.1), rendered)?;
+        output.add(rendered_pair.1, rendered);
     }
 
     // Static assets
-    let assets = ["diagram.json", "wokwi.toml", "chip.wasm", "chip.json"];
-    for asset in assets {
+    for asset in STATIC_ASSETS {
         let src = config_dir.join(asset);
         if src.exists() {
-            fs::copy(&src, project_path.join(asset))?;
+            output.add(asset, fs::read(&src)?);
         }
     }
     Ok(())
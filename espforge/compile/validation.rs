@@ -1,25 +1,120 @@
-use crate::config::EspforgeConfiguration;
-use crate::nibblers::{NibblerDispatcher, NibblerStatus};
-use anyhow::{Context, Result};
-use std::fs;
+use super::CompileOptions;
+use crate::config::{
+    EspforgeConfiguration, LoadOptions, PlatformConfig, SourceMap, UndefinedSubstitutions,
+};
+use crate::nibblers::{Finding, NibblerDispatcher, NibblerResult, NibblerStatus, Severity};
+use crate::resolver::ConfigPath;
+use anyhow::Result;
 use std::path::Path;
 
-pub fn load_and_validate(path: &Path) -> Result<EspforgeConfiguration> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read config at {}", path.display()))?;
-    
-    let config: EspforgeConfiguration = serde_yaml_ng::from_str(&content)
-        .context("Failed to parse YAML configuration")?;
+pub fn load_and_validate(
+    path: &Path,
+    options: &CompileOptions,
+) -> Result<(EspforgeConfiguration, SourceMap)> {
+    let (config, source_map) = load_config(path, options).inspect_err(|e| {
+        let findings = substitution_findings(e);
+        if !findings.is_empty() {
+            let source_map = SourceMap::read(path);
+            for finding in findings {
+                eprint!("{}", render_finding(path, &source_map, &finding));
+            }
+        }
+    })?;
+    validate_with_nibblers(&config, path, &source_map)?;
+    Ok((config, source_map))
+}
+
+/// Reads and parses the configuration, applying command-line overrides.
+///
+/// The returned [`SourceMap`] locates findings and resolver errors in the file
+/// or in the fragments it includes.
+pub fn load_config(
+    path: &Path,
+    options: &CompileOptions,
+) -> Result<(EspforgeConfiguration, SourceMap)> {
+    // Retarget before validating so chip-specific nibblers check against the new chip
+    let load_options = LoadOptions {
+        platform: options
+            .override_platform
+            .as_deref()
+            .map(str::parse::<PlatformConfig>)
+            .transpose()?,
+        profile: options.profile.clone(),
+    };
+    let (mut config, source_map) = EspforgeConfiguration::load_located(path, &load_options)?;
+    if let Some(backend) = options.backend {
+        config
+            .espforge
+            .generator
+            .get_or_insert_with(Default::default)
+            .backend = backend;
+    }
+
+    Ok((config, source_map))
+}
+
+/// One error finding per undefined `${...}` reference behind a failed
+/// [`load_config`]; none for other errors.
+pub fn substitution_findings(error: &anyhow::Error) -> Vec<Finding> {
+    error
+        .downcast_ref::<UndefinedSubstitutions>()
+        .map(|undefined| {
+            undefined
+                .0
+                .iter()
+                .map(|u| Finding::error("substitution-undefined", u.path.clone(), u.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-    validate_with_nibblers(&config)?;
-    Ok(config)
+pub fn run_nibblers(config: &EspforgeConfiguration) -> Vec<NibblerResult> {
+    NibblerDispatcher::new().process_config(config)
 }
 
-fn validate_with_nibblers(config: &EspforgeConfiguration) -> Result<()> {
-    let dispatcher = NibblerDispatcher::new();
-    let results = dispatcher.process_config(config);
-    
+/// Renders a finding rustc-style, with a code frame when its path is in the file.
+pub fn render_finding(file: &Path, source_map: &SourceMap, finding: &Finding) -> String {
+    format!(
+        "{}[{}]: {}\n{}",
+        finding.severity,
+        finding.code,
+        finding.message,
+        render_location(file, source_map, &finding.path)
+    )
+}
+
+/// Renders `--> file:line:column` and a code frame for a configuration path,
+/// naming the included fragment instead of `file` when the node comes from one.
+pub fn render_location(file: &Path, source_map: &SourceMap, path: &str) -> String {
+    match source_map.locate(path) {
+        Some(location) => format!(
+            "  --> {}:{}:{}\n{}",
+            location.file.unwrap_or(file).display(),
+            location.span.line,
+            location.span.column,
+            source_map.code_frame(location)
+        ),
+        None if !path.is_empty() => format!("  --> {} ({})\n", file.display(), path),
+        None => String::new(),
+    }
+}
+
+/// Prints where a resolver error carrying a [`ConfigPath`] points in the file.
+pub fn report_located_error(file: &Path, source_map: &SourceMap, error: &anyhow::Error) {
+    if let Some(ConfigPath(path)) = error.downcast_ref::<ConfigPath>() {
+        eprint!("{}", render_location(file, source_map, path));
+    }
+}
+
+fn validate_with_nibblers(
+    config: &EspforgeConfiguration,
+    file: &Path,
+    source_map: &SourceMap,
+) -> Result<()> {
+    let results = run_nibblers(config);
+
     let mut error_log = String::new();
+    let mut warnings = String::new();
     let mut has_errors = false;
 
     for res in results {
@@ -27,8 +122,13 @@ fn validate_with_nibblers(config: &EspforgeConfiguration) -> Result<()> {
         if !res.findings.is_empty() {
             error_log.push_str(&format!("== {} ==\n", res.nibbler_name));
             for finding in res.findings {
-                let icon = if res.status == NibblerStatus::Error { "❌" } else { "⚠️" };
-                error_log.push_str(&format!("  {} {}\n", icon, finding));
+                match finding.severity {
+                    Severity::Info => error_log.push_str(&format!("  ✔️ {}\n", finding)),
+                    _ => error_log.push_str(&render_finding(file, source_map, &finding)),
+                }
+                if finding.severity == Severity::Warning {
+                    warnings.push_str(&render_finding(file, source_map, &finding));
+                }
             }
         }
     }
@@ -37,5 +137,7 @@ fn validate_with_nibblers(config: &EspforgeConfiguration) -> Result<()> {
         eprintln!("{}", error_log);
         anyhow::bail!("Configuration validation failed.");
     }
+    // Warnings do not stop the compile, but should not go unnoticed either
+    eprint!("{}", warnings);
     Ok(())
 }
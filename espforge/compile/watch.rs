@@ -0,0 +1,196 @@
+use super::postprocess::{RENDERED_FILES, STATIC_ASSETS};
+use super::validation::load_config;
+use super::{CompileOptions, compile};
+use crate::config::load_document;
+use crate::generate::local_roots;
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use notify_debouncer_mini::new_debouncer;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+/// How long files must stay untouched before a change triggers a recompile,
+/// so that an editor's save (often several writes) compiles once.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Compiles `path`, then recompiles whenever the configuration, a file it
+/// includes, a local file it pulls in (`app.ruchy`, `Cargo.toml.tera`, wokwi
+/// files and assets) or a local component or device changes. Errors are
+/// printed and watching carries on.
+pub fn watch(path: &Path, options: CompileOptions) -> Result<()> {
+    let config_dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut local = vec![
+        path.file_name()
+            .context("Configuration path has no file name")?,
+        OsStr::new("app.ruchy"),
+        OsStr::new("Cargo.toml.tera"),
+    ];
+    local.extend(RENDERED_FILES.iter().map(|(src, _)| OsStr::new(src)));
+    local.extend(STATIC_ASSETS.iter().map(OsStr::new));
+    let local: Vec<PathBuf> = local.iter().map(|name| config_dir.join(name)).collect();
+
+    // Watch directories rather than files, so inputs created later count too
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(DEBOUNCE, tx).context("Failed to start file watcher")?;
+    let mut watched = BTreeSet::new();
+    let mut inputs = input_files(path, &local, &[]);
+    let mut modules = module_dirs(path, &options, &[]);
+    watch_dirs(debouncer.watcher(), &inputs, &modules, &mut watched)?;
+
+    compile_and_report(path, &options);
+    println!(
+        "Watching {} for changes (Ctrl+C to stop)",
+        config_dir.display()
+    );
+
+    // Compiling reads the inputs, which some platforms report as events too;
+    // only a changed modification time or size counts
+    let mut last = snapshot(&inputs, &modules);
+    for result in rx {
+        match result {
+            Ok(events) => {
+                let touched = events.iter().any(|e| {
+                    inputs
+                        .iter()
+                        .any(|input| input.file_name() == e.path.file_name())
+                        || modules.iter().any(|dir| e.path.starts_with(dir))
+                });
+                let current = snapshot(&inputs, &modules);
+                if touched && current != last {
+                    println!("\nChange detected, recompiling {}", path.display());
+                    compile_and_report(path, &options);
+
+                    // The included files and module paths may have changed
+                    inputs = input_files(path, &local, &inputs);
+                    modules = module_dirs(path, &options, &modules);
+                    if let Err(e) = watch_dirs(debouncer.watcher(), &inputs, &modules, &mut watched)
+                    {
+                        eprintln!("Error: {:#}", e);
+                    }
+                    last = snapshot(&inputs, &modules);
+                }
+            }
+            Err(e) => eprintln!("Watch error: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// The local inputs plus every file the configuration includes. Keeps the
+/// `previous` list while the configuration does not load.
+fn input_files(path: &Path, local: &[PathBuf], previous: &[PathBuf]) -> Vec<PathBuf> {
+    let Ok(document) = load_document(path) else {
+        return if previous.is_empty() {
+            local.to_vec()
+        } else {
+            previous.to_vec()
+        };
+    };
+    let mut inputs = local.to_vec();
+    for file in document.files {
+        if !inputs.contains(&file) {
+            inputs.push(file);
+        }
+    }
+    inputs
+}
+
+/// The `components/` and `devices/` directories of the configuration's local
+/// roots (see [`local_roots`]). Keeps the `previous` list while the
+/// configuration does not load.
+fn module_dirs(path: &Path, options: &CompileOptions, previous: &[PathBuf]) -> Vec<PathBuf> {
+    let Ok((config, _)) = load_config(path, options) else {
+        return previous.to_vec();
+    };
+    let mut dirs = Vec::new();
+    for root in local_roots(&config) {
+        for kind in ["components", "devices"] {
+            // Canonical, so that the watcher's event paths start with it
+            if let Ok(dir) = root.join(kind).canonicalize()
+                && dir.is_dir()
+                && !dirs.contains(&dir)
+            {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// Watches the directory of each input, and each module directory with
+/// everything below it. A directory that fails is tried again next time.
+fn watch_dirs(
+    watcher: &mut dyn Watcher,
+    inputs: &[PathBuf],
+    modules: &[PathBuf],
+    watched: &mut BTreeSet<PathBuf>,
+) -> Result<()> {
+    let parents = inputs.iter().map(|input| match input.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    });
+    let dirs = parents
+        .map(|dir| (dir, RecursiveMode::NonRecursive))
+        .chain(modules.iter().map(|dir| (dir.as_path(), RecursiveMode::Recursive)));
+
+    for (dir, mode) in dirs {
+        if !watched.contains(dir) {
+            watcher
+                .watch(dir, mode)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+            watched.insert(dir.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+fn snapshot(inputs: &[PathBuf], modules: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    let mut files = inputs.to_vec();
+    for dir in modules {
+        files_below(dir, &mut files);
+    }
+    files
+        .iter()
+        .map(|file| {
+            let metadata = fs::metadata(file).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+fn files_below(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            files_below(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+fn compile_and_report(path: &Path, options: &CompileOptions) {
+    match compile(path, options.clone()) {
+        Ok(report) => {
+            print!("{}", report);
+            if report.has_conflicts() {
+                eprintln!(
+                    "Some files have local changes and were not updated (use --force to overwrite them)."
+                );
+            }
+        }
+        Err(e) => eprintln!("Error: {:#}", e),
+    }
+}
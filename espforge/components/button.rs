@@ -1,5 +1,6 @@
 
 use crate::platform::gpio::GPIOInput;
+pub use crate::platform::gpio::PinSettings;
 
 pub struct Button {
     input: GPIOInput,
@@ -12,6 +13,13 @@ impl Button {
         }
     }
 
+    /// A button with the pulls and polarity of its GPIO; with `inverted: true`
+    /// a button wired to 3.3V reads as pressed when the pin is high
+    pub fn with_settings(pin: u8, settings: PinSettings) -> Self {
+        Button {
+            input: GPIOInput::with_settings(pin, settings),
+        }
+    }
 
     pub fn is_button_pressed(&self) -> bool {
         self.input.is_low()
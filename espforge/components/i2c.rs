@@ -1,5 +1,6 @@
 use crate::platform::i2c::I2CMaster;
 use core::cell::RefCell;
+use embedded_hal_bus::i2c::RefCellDevice;
 
 pub struct I2C {
     master: RefCell<I2CMaster>,
@@ -24,11 +25,12 @@ impl I2C {
         let _ = self.master.borrow_mut().write(address, &[data]);
     }
 
-    /// Consumes this component wrapper and returns the underlying Platform Driver.
-    /// 
-    /// This is used by "Devices" (e.g., Display Drivers) that need ownership 
-    /// of the generic `embedded-hal` I2C interface.
-    pub fn into_inner(self) -> I2CMaster {
-        self.master.into_inner()
+    /// Returns a handle to the shared bus for one device.
+    ///
+    /// This is used by "Devices" (e.g., Display Drivers) that need the generic
+    /// `embedded-hal` I2C interface. Several devices can each hold a handle, so
+    /// an OLED, a sensor and an RTC can sit on the same SDA/SCL lines.
+    pub fn device(&self) -> RefCellDevice<'_, I2CMaster> {
+        RefCellDevice::new(&self.master)
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,6 @@
 
 use crate::platform::gpio::GPIOOutput;
+pub use crate::platform::gpio::PinSettings;
 
 pub struct LED {
     output: GPIOOutput,
@@ -12,6 +13,14 @@ impl LED {
         }
     }
 
+    /// An LED with the drive mode, initial state and polarity of its GPIO,
+    /// e.g. an active-low LED with `inverted: true`
+    pub fn with_settings(pin: u8, settings: PinSettings) -> Self {
+        LED {
+            output: GPIOOutput::with_settings(pin, settings),
+        }
+    }
+
     pub fn on(&mut self) {
         self.output.set_high();
     }
@@ -1,11 +0,0 @@
-pub mod led;
-pub use led::*;
-pub mod button;
-pub use button::*;
-pub mod i2c;
-pub use i2c::*;
-pub mod spi;
-pub use spi::*;
-pub mod uart;
-pub use uart::*;
-
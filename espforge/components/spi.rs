@@ -1,7 +1,10 @@
 use crate::platform::spi::SPIMaster;
+use core::cell::RefCell;
+use embedded_hal_bus::spi::{NoDelay, RefCellDevice};
+use esp_hal::gpio::{AnyPin, Level, Output, OutputConfig};
 
 pub struct SPI {
-    master: SPIMaster,
+    master: RefCell<SPIMaster>,
 }
 
 impl SPI {
@@ -9,23 +12,30 @@ impl SPI {
     // If cs is > 255 (e.g. -1 cast to unsigned), we assume no CS.
     pub fn new(spi_bus: u8, sck: u8, mosi: u8, miso: u8, cs: u8, frequency: u32, mode: u8) -> Self {
         SPI {
-            master: SPIMaster::new(spi_bus, sck, mosi, miso, cs, frequency, mode)
+            master: RefCell::new(SPIMaster::new(spi_bus, sck, mosi, miso, cs, frequency, mode)),
         }
     }
 
 
-    pub fn write_read(&mut self, data: u8) -> u8 {
+    pub fn write_read(&self, data: u8) -> u8 {
         let mut write_buffer = [data, 0x00];
 
         // Use the stored CS pin
-        let _ = self.master.transfer(&mut write_buffer);
+        let _ = self.master.borrow_mut().transfer(&mut write_buffer);
 
         // Return the byte received during the second cycle
         write_buffer[1]
     }
 
-    pub fn into_inner(self) -> SPIMaster {
-        self.master
+    /// Returns a handle to the shared bus for one device, selected by its own
+    /// chip-select pin.
+    ///
+    /// Leave `cs` out of the bus config when devices share it, otherwise the
+    /// hardware CS is asserted for every device.
+    pub fn device(&self, cs_pin: u8) -> RefCellDevice<'_, SPIMaster, Output<'static>, NoDelay> {
+        let cs_any = unsafe { AnyPin::steal(cs_pin) };
+        let cs = Output::new(cs_any, Level::High, OutputConfig::default());
+
+        RefCellDevice::new_no_delay(&self.master, cs).unwrap()
     }
 }
-
@@ -1,23 +1,27 @@
 use std::collections::HashMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng::Value;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct AppConfig {
     #[serde(default)]
     pub variables: HashMap<String, VariableConfig>,
 
     #[serde(default)]
+    #[schemars(with = "Vec<HashMap<String, serde_json::Value>>")]
     pub setup: Vec<HashMap<String, Value>>,
 
     #[serde(default, rename = "loop")]
+    #[schemars(with = "Vec<HashMap<String, serde_json::Value>>")]
     pub loop_fn: Vec<HashMap<String, Value>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct VariableConfig {
     #[serde(rename = "type")]
     pub type_name: String,
+    #[schemars(with = "serde_json::Value")]
     pub initial: Value,
 }
 
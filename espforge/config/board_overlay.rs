@@ -0,0 +1,172 @@
+use crate::board::{BoardDefinition, load_board};
+use anyhow::{Result, bail};
+use serde_yaml_ng::{Mapping, Value};
+
+/// The `espforge:` key naming a bundled board definition.
+pub const BOARD_KEY: &str = "board";
+
+/// Whether `document` names a board in `espforge.board`.
+pub fn is_used(document: &Value) -> bool {
+    document
+        .get("espforge")
+        .and_then(|e| e.get(BOARD_KEY))
+        .is_some()
+}
+
+/// Applies the board named by `espforge.board`, if any, to `document`.
+///
+/// `espforge.platform` and `espforge.wokwi_board` default to the board's chip
+/// and Wokwi part. Pins in `esp32:` may be board labels such as `D4` or
+/// `LED_BUILTIN`, and `i2c`, `spi` and `uart` entries take the board's bus
+/// pins for the ones they leave out.
+pub fn apply_board(document: &mut Value) -> Result<()> {
+    let name = match document.get("espforge").and_then(|e| e.get(BOARD_KEY)) {
+        Some(Value::String(name)) => name.clone(),
+        Some(_) => bail!("espforge.{} must be a board name", BOARD_KEY),
+        None => return Ok(()),
+    };
+    let board = load_board(&name)?;
+
+    if let Some(Value::Mapping(espforge)) = document.get_mut("espforge") {
+        set_default(espforge, "platform", board.board.chip.to_string());
+        if let Some(wokwi) = board.board.wokwi {
+            set_default(espforge, "wokwi_board", serde_yaml_ng::to_value(wokwi)?);
+        }
+    }
+
+    let Some(Value::Mapping(esp32)) = document.get_mut("esp32") else {
+        return Ok(());
+    };
+    for (section, entries) in esp32.iter_mut() {
+        let (Some(section), Value::Mapping(entries)) = (section.as_str(), entries) else {
+            continue;
+        };
+        for (entry_name, entry) in entries.iter_mut() {
+            let Value::Mapping(entry) = entry else {
+                continue;
+            };
+            let path = format!(
+                "esp32.{}.{}",
+                section,
+                entry_name.as_str().unwrap_or_default()
+            );
+            for (field, default) in bus_pins(&board, section) {
+                let pin = match entry.get(field) {
+                    Some(Value::String(label)) => match board.pin(label) {
+                        Some(pin) => pin,
+                        None => bail!(
+                            "Unknown pin '{}' at {}.{}: board '{}' has {}",
+                            label,
+                            path,
+                            field,
+                            name,
+                            board.labels().join(", ")
+                        ),
+                    },
+                    Some(_) => continue,
+                    None => match default {
+                        Some(pin) => pin,
+                        None => continue,
+                    },
+                };
+                entry.insert(Value::from(field), Value::from(pin));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Undoes [`apply_board`] on `document`, a configuration serialized after
+/// editing, as far as `original` (the file before editing) relied on the board.
+///
+/// Pins `original` wrote as labels are written as labels again, and values it
+/// left to the board are left out again, as long as they did not change.
+pub fn restore_board(document: &mut Value, original: &Value) -> Result<()> {
+    let Some(name) = document
+        .get("espforge")
+        .and_then(|e| e.get(BOARD_KEY))
+        .and_then(Value::as_str)
+    else {
+        return Ok(());
+    };
+    let board = load_board(name)?;
+
+    if let Some(Value::Mapping(espforge)) = document.get_mut("espforge") {
+        let written = original.get("espforge");
+        let mut defaults = vec![("platform", Value::from(board.board.chip.to_string()))];
+        if let Some(wokwi) = board.board.wokwi {
+            defaults.push(("wokwi_board", serde_yaml_ng::to_value(wokwi)?));
+        }
+        for (key, default) in defaults {
+            if written.and_then(|e| e.get(key)).is_none() && espforge.get(key) == Some(&default) {
+                espforge.shift_remove(key);
+            }
+        }
+    }
+
+    let Some(Value::Mapping(esp32)) = document.get_mut("esp32") else {
+        return Ok(());
+    };
+    for (section, entries) in esp32.iter_mut() {
+        let (Some(section), Value::Mapping(entries)) = (section.as_str(), entries) else {
+            continue;
+        };
+        for (entry_name, entry) in entries.iter_mut() {
+            let Value::Mapping(entry) = entry else {
+                continue;
+            };
+            // Entries added while editing keep the numbers they were given
+            let Some(written) = original
+                .get("esp32")
+                .and_then(|e| e.get(section))
+                .and_then(|e| e.get(entry_name))
+            else {
+                continue;
+            };
+            for (field, default) in bus_pins(&board, section) {
+                let Some(pin) = entry.get(field).and_then(Value::as_u64) else {
+                    continue;
+                };
+                match written.get(field) {
+                    Some(Value::String(label))
+                        if board.pin(label).is_some_and(|p| u64::from(p) == pin) =>
+                    {
+                        entry.insert(Value::from(field), Value::from(label.clone()));
+                    }
+                    None if default.is_some_and(|p| u64::from(p) == pin) => {
+                        entry.shift_remove(field);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The pin fields of an `esp32.<section>` entry, with the board's default for
+/// each one.
+fn bus_pins(board: &BoardDefinition, section: &str) -> Vec<(&'static str, Option<u8>)> {
+    let i2c = board.i2c.as_ref();
+    let spi = board.spi.as_ref();
+    let uart = board.uart.as_ref();
+    match section {
+        "gpio" => vec![("pin", None)],
+        "i2c" => vec![("sda", i2c.map(|i| i.sda)), ("scl", i2c.map(|i| i.scl))],
+        "spi" => vec![
+            ("sck", spi.map(|s| s.sck)),
+            ("mosi", spi.map(|s| s.mosi)),
+            ("miso", spi.map(|s| s.miso)),
+            // Each device on a shared bus has its own chip select
+            ("cs", None),
+        ],
+        "uart" => vec![("tx", uart.map(|u| u.tx)), ("rx", uart.map(|u| u.rx))],
+        _ => Vec::new(),
+    }
+}
+
+fn set_default(map: &mut Mapping, key: &str, value: impl Into<Value>) {
+    if !map.contains_key(key) {
+        map.insert(Value::from(key), value.into());
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng::Value;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct DeviceConfig {
     pub using: String,
     #[serde(default)]
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
     pub with: HashMap<String, Value>,
 }
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct Esp32Config {
     #[serde(default)]
     pub gpio: HashMap<String, GpioPinConfig>,
@@ -23,7 +24,7 @@ impl Esp32Config {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct UartConfig {
     #[serde(default)]
     pub uart: u8,
@@ -33,7 +34,7 @@ pub struct UartConfig {
     pub baud: u32,    
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct SpiConfig {
     #[serde(default)]
     pub spi: u8, 
@@ -49,7 +50,7 @@ pub struct SpiConfig {
     pub mode: u8,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct I2cConfig {
     #[serde(default)]
     pub i2c: u8,
@@ -71,7 +72,10 @@ fn default_uart_baud() -> u32 {
     9600
 }
 
-#[derive(Debug, Deserialize, Serialize,Clone, Copy)]
+/// The output drive strengths esp-hal supports, in mA.
+pub const DRIVE_STRENGTHS: [u8; 4] = [5, 10, 20, 40];
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, JsonSchema)]
 pub struct GpioPinConfig {
     pub pin: u8,
     pub direction: PinDirection,
@@ -79,12 +83,46 @@ pub struct GpioPinConfig {
     pub pullup: bool,
     #[serde(default)]
     pub pulldown: bool,
+    /// Only drive the pin low and let it float high (e.g. shared or 5V lines)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub open_drain: bool,
+    /// Output drive strength in mA: 5, 10, 20 or 40 (esp-hal defaults to 20)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_strength: Option<u8>,
+    /// Logical level of an output at startup, before the first write. With
+    /// `inverted`, `initial: high` starts the pin at a low level.
+    #[serde(default, skip_serializing_if = "PinLevel::is_low")]
+    pub initial: PinLevel,
+    /// Active-low: a high level in the app drives (or reads) the pin low
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inverted: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq,  Clone, Copy)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum PinDirection {
     Input,
     Output,
+    /// An output that can also be read back, e.g. an open-drain data line
+    InputOutput,
+}
+
+impl PinDirection {
+    pub fn is_output(&self) -> bool {
+        matches!(self, PinDirection::Output | PinDirection::InputOutput)
+    }
 }
 
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PinLevel {
+    #[default]
+    Low,
+    High,
+}
+
+impl PinLevel {
+    pub fn is_low(&self) -> bool {
+        *self == PinLevel::Low
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_yaml_ng::Value;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ExampleConfig {
     pub name: String,
     #[serde(flatten)]
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
     pub example_properties: HashMap<String, Value>,
 }
\ No newline at end of file
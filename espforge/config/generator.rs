@@ -0,0 +1,192 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the Rust project skeleton is created before espforge fills it in.
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct GeneratorConfig {
+    #[serde(default)]
+    pub backend: GeneratorBackend,
+    /// esp-generate options (`-o`). Defaults to [`GeneratorConfig::DEFAULT_OPTIONS`];
+    /// `embassy` is added when `enable_async` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<GeneratorOption>>,
+}
+
+impl GeneratorConfig {
+    /// The options espforge has always generated projects with.
+    pub const DEFAULT_OPTIONS: [GeneratorOption; 5] = [
+        GeneratorOption::Log,
+        GeneratorOption::UnstableHal,
+        GeneratorOption::EspBacktrace,
+        GeneratorOption::Wokwi,
+        GeneratorOption::Vscode,
+    ];
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq, Clone, Copy, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum GeneratorBackend {
+    /// Run the external `esp-generate` tool
+    #[default]
+    EspGenerate,
+    /// Write the skeleton natively, without any external tool
+    Builtin,
+}
+
+impl GeneratorBackend {
+    pub const ALL: [GeneratorBackend; 2] =
+        [GeneratorBackend::EspGenerate, GeneratorBackend::Builtin];
+}
+
+impl fmt::Display for GeneratorBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeneratorBackend::EspGenerate => write!(f, "esp-generate"),
+            GeneratorBackend::Builtin => write!(f, "builtin"),
+        }
+    }
+}
+
+impl FromStr for GeneratorBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_yaml_ng::from_str(s).map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid generator backend: {} (expected esp-generate or builtin)",
+                s
+            )
+        })
+    }
+}
+
+/// An esp-generate option. Unknown names are rejected when the file is parsed.
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum GeneratorOption {
+    /// Heap allocations via esp-alloc
+    Alloc,
+    /// Wi-Fi support
+    #[serde(alias = "esp-wifi")]
+    Wifi,
+    /// Bluetooth LE support
+    Ble,
+    /// Async with embassy (set by `enable_async`)
+    Embassy,
+    /// Flash and monitor with probe-rs instead of espflash
+    ProbeRs,
+    /// Logging with defmt
+    Defmt,
+    /// Logging with the `log` crate
+    Log,
+    /// Panic handler and backtraces via esp-backtrace
+    EspBacktrace,
+    /// Panic handler that prints over RTT (needs probe-rs)
+    PanicRttTarget,
+    /// On-device tests with embedded-test (needs probe-rs)
+    EmbeddedTest,
+    /// Enable esp-hal's unstable APIs, which espforge's platform layer uses
+    UnstableHal,
+    /// Wokwi simulator configuration
+    Wokwi,
+    Vscode,
+    Helix,
+    Zed,
+    Neovim,
+    StackSmashingProtection,
+}
+
+impl GeneratorOption {
+    pub const ALL: [GeneratorOption; 17] = [
+        GeneratorOption::Alloc,
+        GeneratorOption::Wifi,
+        GeneratorOption::Ble,
+        GeneratorOption::Embassy,
+        GeneratorOption::ProbeRs,
+        GeneratorOption::Defmt,
+        GeneratorOption::Log,
+        GeneratorOption::EspBacktrace,
+        GeneratorOption::PanicRttTarget,
+        GeneratorOption::EmbeddedTest,
+        GeneratorOption::UnstableHal,
+        GeneratorOption::Wokwi,
+        GeneratorOption::Vscode,
+        GeneratorOption::Helix,
+        GeneratorOption::Zed,
+        GeneratorOption::Neovim,
+        GeneratorOption::StackSmashingProtection,
+    ];
+
+    /// The name passed to `esp-generate -o`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeneratorOption::Alloc => "alloc",
+            GeneratorOption::Wifi => "wifi",
+            GeneratorOption::Ble => "ble",
+            GeneratorOption::Embassy => "embassy",
+            GeneratorOption::ProbeRs => "probe-rs",
+            GeneratorOption::Defmt => "defmt",
+            GeneratorOption::Log => "log",
+            GeneratorOption::EspBacktrace => "esp-backtrace",
+            GeneratorOption::PanicRttTarget => "panic-rtt-target",
+            GeneratorOption::EmbeddedTest => "embedded-test",
+            GeneratorOption::UnstableHal => "unstable-hal",
+            GeneratorOption::Wokwi => "wokwi",
+            GeneratorOption::Vscode => "vscode",
+            GeneratorOption::Helix => "helix",
+            GeneratorOption::Zed => "zed",
+            GeneratorOption::Neovim => "neovim",
+            GeneratorOption::StackSmashingProtection => "stack-smashing-protection",
+        }
+    }
+
+    /// Options that must be selected alongside this one.
+    pub fn requires(&self) -> &'static [GeneratorOption] {
+        match self {
+            GeneratorOption::Wifi | GeneratorOption::Ble => {
+                &[GeneratorOption::Alloc, GeneratorOption::UnstableHal]
+            }
+            GeneratorOption::Embassy => &[GeneratorOption::UnstableHal],
+            GeneratorOption::PanicRttTarget | GeneratorOption::EmbeddedTest => {
+                &[GeneratorOption::ProbeRs]
+            }
+            _ => &[],
+        }
+    }
+
+    /// Options that cannot be selected together with this one.
+    pub fn conflicts(&self) -> &'static [GeneratorOption] {
+        match self {
+            GeneratorOption::Log => &[GeneratorOption::Defmt],
+            GeneratorOption::Defmt => &[GeneratorOption::Log],
+            GeneratorOption::EspBacktrace => &[GeneratorOption::PanicRttTarget],
+            GeneratorOption::PanicRttTarget => &[GeneratorOption::EspBacktrace],
+            _ => &[],
+        }
+    }
+
+    /// Whether the builtin backend implements this option; the rest are
+    /// dropped when it writes the skeleton.
+    pub fn builtin_supported(&self) -> bool {
+        matches!(
+            self,
+            GeneratorOption::Alloc
+                | GeneratorOption::Embassy
+                | GeneratorOption::Defmt
+                | GeneratorOption::Log
+                | GeneratorOption::EspBacktrace
+                | GeneratorOption::UnstableHal
+                | GeneratorOption::Wokwi
+        )
+    }
+}
+
+impl fmt::Display for GeneratorOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -0,0 +1,392 @@
+use super::SourceMap;
+use anyhow::{Context, Result, bail};
+use serde_yaml_ng::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Tag replacing a node with the contents of another YAML file.
+pub const INCLUDE_TAG: &str = "!include";
+
+/// The top-level key listing fragments merged into a configuration.
+pub const PACKAGES_KEY: &str = "packages";
+
+/// A configuration with its `!include`s resolved and `packages:` merged.
+#[derive(Debug)]
+pub struct Document {
+    pub value: Value,
+    /// Every file read, the configuration itself first.
+    pub files: Vec<PathBuf>,
+    /// Where each node of `value` came from, across all of `files`.
+    pub source_map: SourceMap,
+}
+
+/// Reads the configuration at `path`.
+///
+/// `key: !include other.yaml` replaces the node with the contents of
+/// `other.yaml`, relative to the file containing the tag. `packages:` is a list
+/// of fragments (file paths, `!include`s or inline mappings) deep-merged into
+/// the configuration, in order, before the file's own content: mappings merge
+/// key by key, lists such as `app.setup` are concatenated, and a key set to two
+/// different values is an error.
+pub fn load_document(path: &Path) -> Result<Document> {
+    let mut files = Vec::new();
+    let (value, source_map) = load_file(path, &mut Vec::new(), &mut files)?;
+    Ok(Document {
+        value,
+        files,
+        source_map,
+    })
+}
+
+/// Whether a parsed configuration has a top-level `packages:` or an
+/// `!include`-tagged node, i.e. needs [`load_document`] to be complete.
+pub fn needs_loading(document: &Value) -> bool {
+    document.get(PACKAGES_KEY).is_some() || has_include(document)
+}
+
+fn has_include(value: &Value) -> bool {
+    match value {
+        Value::Tagged(tagged) => tagged.tag == INCLUDE_TAG || has_include(&tagged.value),
+        Value::Mapping(map) => map.values().any(has_include),
+        Value::Sequence(items) => items.iter().any(has_include),
+        _ => false,
+    }
+}
+
+fn load_file(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(Value, SourceMap)> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read config at {}", path.display()))?;
+    if stack.contains(&canonical) {
+        bail!("{} includes itself", path.display());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config at {}", path.display()))?;
+    let mut value: Value = serde_yaml_ng::from_str(&content)
+        .with_context(|| format!("Failed to parse YAML in {}", path.display()))?;
+    if !files.contains(&path.to_path_buf()) {
+        files.push(path.to_path_buf());
+    }
+    // Locations in the configuration itself are reported against its own name
+    let source_path = (!stack.is_empty()).then(|| path.to_path_buf());
+    let mut source_map = SourceMap::parse_file(source_path, &content);
+
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let result = resolve_includes(&mut value, "", &mut source_map, dir, stack, files)
+        .and_then(|()| merge_packages(value, source_map, path, dir, stack, files));
+    stack.pop();
+    result
+}
+
+fn resolve_includes(
+    value: &mut Value,
+    node_path: &str,
+    source_map: &mut SourceMap,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    match value {
+        Value::Tagged(tagged) if tagged.tag == INCLUDE_TAG => {
+            let Value::String(file) = &tagged.value else {
+                bail!("{} expects a file path", INCLUDE_TAG);
+            };
+            let (included, included_map) = load_file(&dir.join(file), stack, files)?;
+            *value = included;
+            source_map.split_off(node_path);
+            source_map.insert_at(node_path, included_map);
+        }
+        Value::Tagged(tagged) => {
+            resolve_includes(&mut tagged.value, node_path, source_map, dir, stack, files)?
+        }
+        Value::Mapping(map) => {
+            for (key, child) in map.iter_mut() {
+                let child_path = match key.as_str() {
+                    Some(name) => join(node_path, name),
+                    None => continue,
+                };
+                resolve_includes(child, &child_path, source_map, dir, stack, files)?;
+            }
+        }
+        Value::Sequence(items) => {
+            for (index, child) in items.iter_mut().enumerate() {
+                let child_path = format!("{}[{}]", node_path, index);
+                resolve_includes(child, &child_path, source_map, dir, stack, files)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Merges the `packages:` of the file at `path` (already resolved) and then
+/// the rest of the file.
+fn merge_packages(
+    value: Value,
+    mut source_map: SourceMap,
+    path: &Path,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(Value, SourceMap)> {
+    let Value::Mapping(mut own) = value else {
+        return Ok((value, source_map));
+    };
+    let Some(packages) = own.remove(PACKAGES_KEY) else {
+        return Ok((Value::Mapping(own), source_map));
+    };
+    let Value::Sequence(packages) = packages else {
+        bail!("{}: '{}' must be a list", path.display(), PACKAGES_KEY);
+    };
+    let mut package_maps = source_map.split_off(PACKAGES_KEY);
+
+    let mut merged = Value::Mapping(Mapping::new());
+    let mut merged_map = SourceMap::default();
+    for (index, package) in packages.into_iter().enumerate() {
+        let (fragment, fragment_map, origin) = match package {
+            Value::String(file) => {
+                let file = dir.join(file);
+                let origin = file.display().to_string();
+                let (fragment, fragment_map) = load_file(&file, stack, files)?;
+                (fragment, fragment_map, origin)
+            }
+            fragment @ Value::Mapping(_) => (
+                fragment,
+                package_maps.split_off(&format!("[{}]", index)),
+                format!("{}[{}] in {}", PACKAGES_KEY, index, path.display()),
+            ),
+            _ => bail!(
+                "{}: {}[{}] must be a file path or a mapping",
+                path.display(),
+                PACKAGES_KEY,
+                index
+            ),
+        };
+        merge_located(&mut merged, &mut merged_map, fragment, fragment_map, &origin)?;
+    }
+    merge_located(
+        &mut merged,
+        &mut merged_map,
+        Value::Mapping(own),
+        source_map,
+        &path.display().to_string(),
+    )?;
+    Ok((merged, merged_map))
+}
+
+/// [`merge`]s `source` into `target`, and the locations of its nodes into
+/// `target_map`; items appended to a list are renumbered after the ones there.
+fn merge_located(
+    target: &mut Value,
+    target_map: &mut SourceMap,
+    source: Value,
+    mut source_map: SourceMap,
+    origin: &str,
+) -> Result<()> {
+    let mut lists = Vec::new();
+    appended_lists(target, &source, "", &mut lists);
+    for (list, offset) in lists {
+        source_map.shift_items(&list, offset);
+    }
+    merge(target, source, "", origin)?;
+    target_map.insert_at("", source_map);
+    Ok(())
+}
+
+/// The lists of `target` that merging `source` appends to, with their length.
+fn appended_lists(target: &Value, source: &Value, path: &str, lists: &mut Vec<(String, usize)>) {
+    match (target, source) {
+        (Value::Mapping(target), Value::Mapping(source)) => {
+            for (key, value) in source {
+                if let (Some(name), Some(existing)) = (key.as_str(), target.get(key)) {
+                    appended_lists(existing, value, &join(path, name), lists);
+                }
+            }
+        }
+        (Value::Sequence(target), Value::Sequence(_)) => lists.push((path.to_string(), target.len())),
+        _ => {}
+    }
+}
+
+fn join(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// Deep-merges `source` (from `origin`) into `target`.
+fn merge(target: &mut Value, source: Value, path: &str, origin: &str) -> Result<()> {
+    match (target, source) {
+        (Value::Mapping(target), Value::Mapping(source)) => {
+            for (key, value) in source {
+                let child_path = match key.as_str() {
+                    Some(name) if path.is_empty() => name.to_string(),
+                    Some(name) => format!("{}.{}", path, name),
+                    None => format!("{}.{:?}", path, key),
+                };
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value, &child_path, origin)?,
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(target), Value::Sequence(source)) => target.extend(source),
+        // An empty key (e.g. `app:`) takes the other side's value
+        (_, Value::Null) => {}
+        (target @ Value::Null, source) => *target = source,
+        (target, source) if *target == source => {}
+        (target, source) => bail!(
+            "Conflicting values for '{}': {} from {} differs from the {} already set",
+            path,
+            describe(&source),
+            origin,
+            describe(target)
+        ),
+    }
+    Ok(())
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Mapping(_) => "a mapping".to_string(),
+        Value::Sequence(_) => "a list".to_string(),
+        other => serde_yaml_ng::to_string(other)
+            .map(|text| format!("'{}'", text.trim_end()))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    fn parse(yaml: &str) -> Value {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn packages_and_includes_are_detected_structurally() {
+        assert!(needs_loading(&parse("packages: [common.yaml]\n")));
+        assert!(needs_loading(&parse("esp32: !include hardware.yaml\n")));
+        assert!(!needs_loading(&parse(
+            "espforge:\n  name: packages\n  platform: esp32c3\napp:\n  setup:\n    - log.info: \"!include\"\n"
+        )));
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        dir.child("blink.yaml")
+            .write_str("esp32: !include parts/hardware.yaml\n")
+            .unwrap();
+        dir.child("parts/hardware.yaml")
+            .write_str("gpio: !include ../blink.yaml\n")
+            .unwrap();
+
+        let error = load_document(&dir.child("blink.yaml")).unwrap_err();
+        assert!(format!("{:#}", error).contains("includes itself"), "{:#}", error);
+
+        // The same fragment used twice side by side is not a cycle
+        dir.child("blink.yaml")
+            .write_str("packages:\n  - parts/led.yaml\n  - parts/led.yaml\n")
+            .unwrap();
+        dir.child("parts/led.yaml")
+            .write_str("esp32:\n  gpio:\n    led: { pin: 8 }\n")
+            .unwrap();
+        assert!(load_document(&dir.child("blink.yaml")).is_ok());
+    }
+
+    #[test]
+    fn includes_resolve_relative_to_their_own_file() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        dir.child("blink.yaml")
+            .write_str("packages:\n  - common/board.yaml\n")
+            .unwrap();
+        dir.child("common/board.yaml")
+            .write_str("esp32: !include hardware/pins.yaml\n")
+            .unwrap();
+        dir.child("common/hardware/pins.yaml")
+            .write_str("gpio: !include ../../shared/gpio.yaml\n")
+            .unwrap();
+        dir.child("shared/gpio.yaml")
+            .write_str("led: { pin: 8 }\n")
+            .unwrap();
+
+        let document = load_document(&dir.child("blink.yaml")).unwrap();
+
+        assert_eq!(document.value, parse("esp32:\n  gpio:\n    led: { pin: 8 }\n"));
+        assert_eq!(document.files.len(), 4);
+        assert_eq!(document.files[0], dir.child("blink.yaml").path());
+    }
+
+    #[test]
+    fn packages_merge_before_the_files_own_keys() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        dir.child("first.yaml")
+            .write_str("espforge:\n  platform: esp32c3\napp:\n  setup:\n    - log.info: \"first\"\n")
+            .unwrap();
+        dir.child("blink.yaml")
+            .write_str("packages:\n  - first.yaml\n  - app:\n      setup:\n        - log.info: \"second\"\nespforge:\n  name: blink\napp:\n  setup:\n    - log.info: \"own\"\n")
+            .unwrap();
+
+        let document = load_document(&dir.child("blink.yaml")).unwrap();
+
+        assert_eq!(
+            document.value,
+            parse("espforge:\n  platform: esp32c3\n  name: blink\napp:\n  setup:\n    - log.info: \"first\"\n    - log.info: \"second\"\n    - log.info: \"own\"\n")
+        );
+
+        // The file's own keys may not override a package either
+        dir.child("blink.yaml")
+            .write_str("packages:\n  - first.yaml\nespforge:\n  platform: esp32s3\n")
+            .unwrap();
+        let error = load_document(&dir.child("blink.yaml")).unwrap_err();
+        assert!(
+            error.to_string().contains("Conflicting values for 'espforge.platform'"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn source_map_follows_fragments() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        dir.child("common/board.yaml")
+            .write_str("esp32:\n  gpio:\n    led: { pin: 8, direction: output }\napp:\n  setup:\n    - log.info: \"board\"\n")
+            .unwrap();
+        dir.child("common/leds.yaml")
+            .write_str("red_led:\n  using: LED\n")
+            .unwrap();
+        dir.child("blink.yaml")
+            .write_str("packages:\n  - common/board.yaml\ncomponents: !include common/leds.yaml\napp:\n  setup:\n    - log.info: \"own\"\n")
+            .unwrap();
+
+        let document = load_document(&dir.child("blink.yaml")).unwrap();
+        let map = &document.source_map;
+
+        let pin = map.locate("esp32.gpio.led.pin").unwrap();
+        assert_eq!(pin.file, Some(dir.child("common/board.yaml").path()));
+        assert_eq!(pin.span.line, 3);
+
+        let using = map.locate("components.red_led.using").unwrap();
+        assert_eq!(using.file, Some(dir.child("common/leds.yaml").path()));
+        assert_eq!(using.span.line, 2);
+
+        // The file's own list items come after the package's
+        let board = map.locate("app.setup[0]").unwrap();
+        assert_eq!((board.file.is_some(), board.span.line), (true, 6));
+        let own = map.locate("app.setup[1]").unwrap();
+        assert_eq!((own.file, own.span.line), (None, 6));
+    }
+}
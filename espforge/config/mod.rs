@@ -1,32 +1,146 @@
-use std::collections::HashMap;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub mod app;
+pub mod board_overlay;
 pub mod components;
 pub mod devices;
 pub mod esp32;
 pub mod example;
+pub mod generator;
+pub mod include;
+pub mod profiles;
 pub mod project;
+pub mod source_map;
+pub mod substitutions;
 
 pub use app::*;
 pub use components::*;
 pub use devices::*;
 pub use esp32::*;
 pub use example::*;
+pub use generator::*;
+pub use include::{Document, load_document};
 pub use project::*;
+pub use source_map::*;
+pub use substitutions::{UndefinedSubstitution, UndefinedSubstitutions};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct EspforgeConfiguration {
+    /// Values for `${name}` references elsewhere in the file
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub substitutions: BTreeMap<String, serde_yaml_ng::Value>,
     pub espforge: EspforgeConfig,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub example: Option<ExampleConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub esp32: Option<Esp32Config>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub components: Option<HashMap<String, ComponentConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub devices: Option<HashMap<String, DeviceConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub app: Option<AppConfig>,
+    /// Directory of the file the configuration was read from; relative paths
+    /// such as `espforge.module_paths` resolve against it.
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+    /// The `profiles:` entry overlaid on the configuration, if any.
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// Selections made on the command line while loading a configuration.
+#[derive(Debug, Default, Clone)]
+pub struct LoadOptions {
+    /// Overrides `espforge.platform`, or the substitution it refers to.
+    pub platform: Option<PlatformConfig>,
+    /// The `profiles:` entry to overlay on the configuration.
+    pub profile: Option<String>,
 }
 
 impl EspforgeConfiguration {
+    /// Parses a configuration: overlays the selected profile, expands `${name}`
+    /// substitutions and `${env:NAME}` references, then applies the board.
+    ///
+    /// Undefined references fail with [`UndefinedSubstitutions`].
+    pub fn parse(content: &str, options: &LoadOptions) -> Result<Self> {
+        let document = serde_yaml_ng::from_str::<serde_yaml_ng::Value>(content).ok();
+        let rewritten = options.profile.is_some()
+            || document
+                .as_ref()
+                .is_some_and(|d| substitutions::is_used(d) || board_overlay::is_used(d));
+
+        // Parsing the text directly keeps line numbers in serde's errors
+        let Some(document) = document.filter(|_| rewritten) else {
+            let mut config: Self =
+                serde_yaml_ng::from_str(content).context("Failed to parse YAML configuration")?;
+            if let Some(platform) = options.platform {
+                config.espforge.platform = platform;
+            }
+            return Ok(config);
+        };
+        Self::from_document(document, options)
+    }
+
+    /// Reads the configuration at `path`: resolves `!include` and merges
+    /// `packages:` (see [`load_document`]), then parses it like [`Self::parse`].
+    pub fn load(path: &Path, options: &LoadOptions) -> Result<Self> {
+        Self::load_located(path, options).map(|(config, _)| config)
+    }
+
+    /// Like [`Self::load`], also returning where each node of the merged
+    /// configuration is defined.
+    pub fn load_located(path: &Path, options: &LoadOptions) -> Result<(Self, SourceMap)> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config at {}", path.display()))?;
+        // Invalid YAML goes through `parse` for its error message
+        let needs_loading = serde_yaml_ng::from_str::<serde_yaml_ng::Value>(&content)
+            .is_ok_and(|document| include::needs_loading(&document));
+        let (mut config, source_map) = if needs_loading {
+            let document = load_document(path)?;
+            (
+                Self::from_document(document.value, options)?,
+                document.source_map,
+            )
+        } else {
+            (Self::parse(&content, options)?, SourceMap::parse(&content))
+        };
+        config.set_base_dir_from(path);
+        Ok((config, source_map))
+    }
+
+    fn from_document(mut document: serde_yaml_ng::Value, options: &LoadOptions) -> Result<Self> {
+        profiles::apply_profile(&mut document, options.profile.as_deref())?;
+        if let Some(platform) = options.platform {
+            substitutions::set_field(
+                &mut document,
+                &["espforge", "platform"],
+                serde_yaml_ng::Value::String(platform.to_string()),
+            );
+        }
+        substitutions::expand(&mut document)?;
+        board_overlay::apply_board(&mut document)?;
+
+        let mut config: Self =
+            serde_yaml_ng::from_value(document).context("Failed to parse YAML configuration")?;
+        if let Some(platform) = options.platform {
+            config.espforge.platform = platform;
+        }
+        config.profile = options.profile.clone();
+        Ok(config)
+    }
+
+    /// Records that the configuration was read from `file`.
+    pub fn set_base_dir_from(&mut self, file: &Path) {
+        self.base_dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+    }
+
     pub fn get_name(&self) -> &str {
         &self.espforge.name
     }
@@ -115,4 +229,145 @@ mod tests {
             panic!("Example was None.");
         }
     }
+
+    #[test]
+    fn source_map_locates_nodes() {
+        let yaml = "espforge:\n  name: blink\nesp32:\n  gpio:\n    led: { pin: 8, direction: output }\napp:\n  setup:\n    - log.info: \"hi\"\n";
+
+        let map = SourceMap::parse(yaml);
+
+        let pin = map.locate("esp32.gpio.led.pin").expect("pin should be located").span;
+        assert_eq!((pin.line, pin.column, pin.length), (5, 17, 1));
+
+        let action = map.locate("app.setup[0].log.info").expect("action should be located").span;
+        assert_eq!((action.line, action.column, action.length), (8, 17, 4));
+
+        // Missing nodes fall back to their closest ancestor
+        let missing = map.locate("esp32.gpio.led.pullup").expect("ancestor should be located").span;
+        assert_eq!(missing.line, 5);
+        assert_eq!(missing.column, 5);
+    }
+
+    #[test]
+    fn board_comment_keeps_line_numbers() {
+        let yaml = "# board: wired as in the README\nespforge:\n  name: blink\n  platform: esp32c3\nesp32:\n  gpio:\n    led: { pin: eight, direction: output }\n";
+
+        let error = EspforgeConfiguration::parse(yaml, &LoadOptions::default()).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("line 7"), "{:#}", error);
+    }
+
+    #[test]
+    fn parse_expands_substitutions() {
+        let yaml = r#"
+            substitutions:
+              chip: esp32c3
+              led_pin: 8
+            espforge:
+              name: blink_${chip}
+              platform: ${chip}
+            esp32:
+              gpio:
+                led: { pin: "${led_pin}", direction: output }
+        "#;
+
+        let options = LoadOptions {
+            platform: Some(PlatformConfig::ESP32S3),
+            ..Default::default()
+        };
+        let config = EspforgeConfiguration::parse(yaml, &options)
+            .expect("substitutions should expand");
+
+        // The override replaces the substitution, so the name follows it
+        assert_eq!(config.espforge.name, "blink_esp32s3");
+        assert_eq!(config.espforge.platform, PlatformConfig::ESP32S3);
+        assert_eq!(config.esp32.unwrap().gpio["led"].pin, 8);
+
+        let error = EspforgeConfiguration::parse("espforge:\n  name: ${nope}\n  platform: esp32c3\n", &LoadOptions::default())
+            .expect_err("undefined substitution should fail");
+        let undefined = error
+            .downcast_ref::<UndefinedSubstitutions>()
+            .expect("error should list the undefined substitutions");
+        assert_eq!(undefined.0[0].path, "espforge.name");
+        assert_eq!(undefined.0[0].reference, "${nope}");
+    }
+
+    #[test]
+    fn parse_applies_board() {
+        let yaml = r#"
+            espforge:
+              name: blink
+              board: xiao-esp32c3
+            esp32:
+              gpio:
+                led: { pin: D10, direction: output }
+              i2c:
+                bus: { i2c: 0 }
+              uart:
+                console: { uart: 1, tx: D6, rx: 4 }
+        "#;
+
+        let config = EspforgeConfiguration::parse(yaml, &LoadOptions::default())
+            .expect("board labels should resolve");
+
+        assert_eq!(config.espforge.platform, PlatformConfig::ESP32C3);
+        assert_eq!(config.espforge.wokwi_board, Some(WokwiBoard::BoardXiaoEsp32C3));
+        let esp32 = config.esp32.unwrap();
+        assert_eq!(esp32.gpio["led"].pin, 10);
+        assert_eq!((esp32.i2c["bus"].sda, esp32.i2c["bus"].scl), (6, 7));
+        assert_eq!((esp32.uart["console"].tx, esp32.uart["console"].rx), (21, 4));
+
+        let error = EspforgeConfiguration::parse(&yaml.replace("D10", "D11"), &LoadOptions::default())
+            .expect_err("unknown label should fail");
+        assert!(error.to_string().contains("Unknown pin 'D11' at esp32.gpio.led.pin"));
+    }
+
+    #[test]
+    fn gpio_initial_is_the_logical_level() {
+        let yaml = r#"
+            espforge:
+              name: relay
+              platform: esp32c3
+            esp32:
+              gpio:
+                coil: { pin: 4, direction: output, initial: high, inverted: true }
+        "#;
+
+        let config = EspforgeConfiguration::parse(yaml, &LoadOptions::default()).unwrap();
+        let coil = &config.esp32.unwrap().gpio["coil"];
+        assert_eq!(coil.initial, PinLevel::High);
+
+        // The template gets the logical level; PinSettings inverts it on the pin
+        let view = crate::resolver::strategies::views::GpioTemplateView::from(coil);
+        assert!(view.initial_high && view.inverted);
+        assert!(view.settings.contains("initial_high: true, inverted: true"));
+    }
+
+    #[test]
+    fn restore_board_keeps_labels() {
+        let yaml = r#"
+            espforge:
+              name: blink
+              board: xiao-esp32c3
+            esp32:
+              gpio:
+                led: { pin: D10, direction: output }
+                button: { pin: D1, direction: input }
+              i2c:
+                bus: { i2c: 0 }
+        "#;
+        let original: serde_yaml_ng::Value = serde_yaml_ng::from_str(yaml).unwrap();
+        let mut config = EspforgeConfiguration::parse(yaml, &LoadOptions::default()).unwrap();
+        config.esp32.as_mut().unwrap().gpio.get_mut("button").unwrap().pin = 9;
+
+        let mut saved = serde_yaml_ng::to_value(&config).unwrap();
+        board_overlay::restore_board(&mut saved, &original).unwrap();
+
+        assert!(saved["espforge"].get("platform").is_none());
+        assert!(saved["espforge"].get("wokwi_board").is_none());
+        assert_eq!(saved["esp32"]["gpio"]["led"]["pin"], "D10");
+        // Edited pins are written as numbers
+        assert_eq!(saved["esp32"]["gpio"]["button"]["pin"], 9);
+        assert!(saved["esp32"]["i2c"]["bus"].get("sda").is_none());
+    }
 }
@@ -0,0 +1,151 @@
+use anyhow::{Result, bail};
+use serde_yaml_ng::Value;
+
+/// The top-level key holding the named partial configurations.
+pub const PROFILES_KEY: &str = "profiles";
+
+/// Removes `profiles:` from `document` and overlays the one named `profile`.
+///
+/// A profile is a partial configuration: mappings (`esp32.uart`, `components`,
+/// `substitutions`, ...) are merged key by key, lists such as `app.setup` are
+/// appended to, and any other value replaces the base one.
+pub fn apply_profile(document: &mut Value, profile: Option<&str>) -> Result<()> {
+    let profiles = match document {
+        Value::Mapping(map) => map.remove(PROFILES_KEY),
+        _ => None,
+    };
+    let Some(name) = profile else {
+        return Ok(());
+    };
+
+    let mut available = Vec::new();
+    if let Some(Value::Mapping(profiles)) = profiles {
+        for (key, overlay_value) in profiles {
+            if key.as_str() == Some(name) {
+                overlay(document, overlay_value);
+                return Ok(());
+            }
+            available.extend(key.as_str().map(str::to_string));
+        }
+    }
+
+    if available.is_empty() {
+        bail!(
+            "Profile '{}' not found: the configuration has no profiles",
+            name
+        );
+    }
+    bail!(
+        "Profile '{}' not found; available profiles: {}",
+        name,
+        available.join(", ")
+    )
+}
+
+fn overlay(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Mapping(target), Value::Mapping(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(existing) => overlay(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(target), Value::Sequence(source)) => target.extend(source),
+        // An empty key in the profile leaves the base value alone
+        (_, Value::Null) => {}
+        (target, source) => *target = source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::substitutions;
+
+    const BASE: &str = "substitutions:\n  period: 1000\nespforge:\n  name: blink\n  platform: esp32c3\nesp32:\n  gpio:\n    led: { pin: 8, direction: output }\napp:\n  setup:\n    - log.info: \"base\"\n  loop:\n    - delay.delay_millis: ${period}\n";
+
+    fn with_profiles(profiles: &str) -> Value {
+        serde_yaml_ng::from_str(&format!("{}profiles:\n{}", BASE, profiles)).unwrap()
+    }
+
+    fn parse(yaml: &str) -> Value {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn unknown_profiles_list_the_available_ones() {
+        let mut document = with_profiles("  dev: {}\n  prod: {}\n");
+        let error = apply_profile(&mut document, Some("test")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Profile 'test' not found; available profiles: dev, prod"
+        );
+
+        let mut document = parse(BASE);
+        let error = apply_profile(&mut document, Some("dev")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Profile 'dev' not found: the configuration has no profiles"
+        );
+    }
+
+    #[test]
+    fn without_a_profile_the_profiles_are_dropped() {
+        let mut document = with_profiles("  dev:\n    espforge:\n      platform: esp32s3\n");
+        apply_profile(&mut document, None).unwrap();
+        assert_eq!(document, parse(BASE));
+    }
+
+    #[test]
+    fn mappings_merge_lists_append_and_scalars_replace() {
+        let mut document = with_profiles(
+            "  dev:\n    espforge:\n      platform: esp32s3\n    esp32:\n      gpio:\n        led: { pin: 5 }\n        button: { pin: 9, direction: input }\n    app:\n      setup:\n        - log.info: \"dev\"\n      loop:\n",
+        );
+        apply_profile(&mut document, Some("dev")).unwrap();
+
+        assert_eq!(document["espforge"]["name"], Value::from("blink"));
+        assert_eq!(document["espforge"]["platform"], Value::from("esp32s3"));
+        assert_eq!(
+            document["esp32"]["gpio"]["led"],
+            parse("{ pin: 5, direction: output }")
+        );
+        assert_eq!(document["esp32"]["gpio"]["button"]["pin"], Value::from(9));
+        assert_eq!(
+            document["app"]["setup"],
+            parse("[{ log.info: base }, { log.info: dev }]")
+        );
+        // An empty key keeps the base value
+        assert_eq!(
+            document["app"]["loop"],
+            parse("[{ delay.delay_millis: \"${period}\" }]")
+        );
+
+        // A value of another kind replaces the base one outright
+        let mut document = with_profiles("  dev:\n    app:\n      setup: { log.info: \"dev\" }\n");
+        apply_profile(&mut document, Some("dev")).unwrap();
+        assert_eq!(document["app"]["setup"], parse("{ log.info: dev }"));
+    }
+
+    #[test]
+    fn profiles_apply_before_substitutions() {
+        let mut document = with_profiles(
+            "  dev:\n    substitutions:\n      period: 100\n      tag: dev\n    app:\n      setup:\n        - log.info: \"${tag} build\"\n",
+        );
+        apply_profile(&mut document, Some("dev")).unwrap();
+        substitutions::expand(&mut document).unwrap();
+
+        // The profile's substitutions are used by the base and its own values
+        assert_eq!(
+            document["app"]["loop"][0]["delay.delay_millis"],
+            Value::from(100)
+        );
+        assert_eq!(
+            document["app"]["setup"][1]["log.info"],
+            Value::from("dev build")
+        );
+    }
+}
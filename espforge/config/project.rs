@@ -1,25 +1,87 @@
+use super::generator::{GeneratorBackend, GeneratorConfig, GeneratorOption};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct EspforgeConfig {
     pub name: String,
     pub platform: PlatformConfig,
-    #[serde(default)]
+    /// A bundled board definition (e.g. `xiao-esp32c3`) providing pin labels,
+    /// default bus pins, and the platform and Wokwi board when they are not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub wokwi_board: Option<WokwiBoard>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub wokwi: Option<WokwiConfig>,
     #[serde(default)]
     pub enable_async: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<GeneratorConfig>,
+    /// Directory the project is generated into, relative to this file.
+    /// Defaults to `./<name>` in the current directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    /// Extra directories with `components/` and `devices/` folders of local
+    /// modules, relative to this file. The folders next to this file are
+    /// always searched, and take precedence.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub module_paths: Vec<PathBuf>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl EspforgeConfig {
+    /// The project directory for a configuration file in `config_dir`.
+    pub fn output_dir(&self, config_dir: &Path) -> PathBuf {
+        match &self.output {
+            Some(output) => config_dir.join(output),
+            None => PathBuf::from(&self.name),
+        }
+    }
+
+    pub fn generator_backend(&self) -> GeneratorBackend {
+        self.generator
+            .as_ref()
+            .map(|g| g.backend)
+            .unwrap_or_default()
+    }
+
+    /// The esp-generate options as written (or the defaults), plus `embassy`
+    /// when `enable_async` is set. Sorted and deduplicated.
+    pub fn requested_generator_options(&self) -> Vec<GeneratorOption> {
+        let mut options = self
+            .generator
+            .as_ref()
+            .and_then(|g| g.options.clone())
+            .unwrap_or_else(|| GeneratorConfig::DEFAULT_OPTIONS.to_vec());
+        if self.enable_async {
+            options.push(GeneratorOption::Embassy);
+        }
+        options.sort();
+        options.dedup();
+        options
+    }
+
+    /// The options the generated project is actually built with: the requested
+    /// ones, minus those the selected backend does not implement.
+    pub fn generator_options(&self) -> Vec<GeneratorOption> {
+        let mut options = self.requested_generator_options();
+        if self.generator_backend() == GeneratorBackend::Builtin {
+            options.retain(|o| o.builtin_supported());
+        }
+        options
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct WokwiConfig {
     pub diagram: Option<String>,
     pub config: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Copy, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum WokwiBoard {
     BoardEsp32DevkitCV4,
@@ -33,7 +95,24 @@ pub enum WokwiBoard {
     BoardXiaoEsp32S3,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+impl WokwiBoard {
+    /// The part type in Wokwi's `diagram.json`.
+    pub fn part_type(&self) -> &'static str {
+        match self {
+            WokwiBoard::BoardEsp32DevkitCV4 => "board-esp32-devkit-c-v4",
+            WokwiBoard::BoardEsp32S2Devkitm1 => "board-esp32-s2-devkitm-1",
+            WokwiBoard::BoardEsp32S3Devkitc1 => "board-esp32-s3-devkitc-1",
+            WokwiBoard::BoardEsp32C3Devkitm1 => "board-esp32-c3-devkitm-1",
+            WokwiBoard::BoardEsp32C6Devkitc1 => "board-esp32-c6-devkitc-1",
+            WokwiBoard::BoardEsp32H2Devkitm1 => "board-esp32-h2-devkitm-1",
+            WokwiBoard::BoardXiaoEsp32C3 => "board-xiao-esp32-c3",
+            WokwiBoard::BoardXiaoEsp32C6 => "board-xiao-esp32-c6",
+            WokwiBoard::BoardXiaoEsp32S3 => "board-xiao-esp32-s3",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize, Clone, Copy, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum PlatformConfig {
     ESP32,
@@ -46,6 +125,24 @@ pub enum PlatformConfig {
 }
 
 impl PlatformConfig {
+    pub const ALL: [PlatformConfig; 7] = [
+        PlatformConfig::ESP32,
+        PlatformConfig::ESP32C2,
+        PlatformConfig::ESP32C3,
+        PlatformConfig::ESP32C6,
+        PlatformConfig::ESP32H2,
+        PlatformConfig::ESP32S2,
+        PlatformConfig::ESP32S3,
+    ];
+
+    /// Xtensa chips need the `esp` toolchain installed by espup.
+    pub fn is_xtensa(&self) -> bool {
+        matches!(
+            self,
+            PlatformConfig::ESP32 | PlatformConfig::ESP32S2 | PlatformConfig::ESP32S3
+        )
+    }
+
     pub fn target(&self) -> &str {
         match self {
             PlatformConfig::ESP32 => "xtensa-esp32-none-elf",
@@ -73,3 +170,10 @@ impl fmt::Display for PlatformConfig {
     }
 }
 
+impl FromStr for PlatformConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_yaml_ng::from_str(s).map_err(|_| anyhow::anyhow!("Invalid platform: {}", s))
+    }
+}
@@ -0,0 +1,299 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+/// Location of a YAML node. Line and column are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+/// Where a node is defined: its span and, for a node pulled in from a
+/// `packages:` or `!include` fragment, the fragment's path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location<'a> {
+    /// `None` for the configuration file itself
+    pub file: Option<&'a Path>,
+    pub span: Span,
+}
+
+/// Maps dotted configuration paths (`esp32.gpio.led.pin`, `app.setup[0]`)
+/// to their position in the YAML source.
+///
+/// Scalars map to their value; mappings and sequences map to the key that
+/// introduces them. A map built by [`super::load_document`] also covers the
+/// fragments the configuration pulls in.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    spans: HashMap<String, (usize, Span)>,
+}
+
+#[derive(Debug)]
+struct SourceFile {
+    path: Option<PathBuf>,
+    lines: Vec<String>,
+}
+
+impl SourceMap {
+    /// Builds the map from raw YAML. Sources that fail to scan yield an empty
+    /// map, since serde reports those errors with their own location.
+    pub fn parse(source: &str) -> Self {
+        Self::parse_file(None, source)
+    }
+
+    /// The map of the configuration at `path` and the fragments it pulls in;
+    /// empty when it cannot be read.
+    pub fn read(path: &Path) -> Self {
+        super::load_document(path)
+            .map(|document| document.source_map)
+            .unwrap_or_default()
+    }
+
+    /// Like [`Self::parse`], for a fragment read from `path`.
+    pub(crate) fn parse_file(path: Option<PathBuf>, source: &str) -> Self {
+        let mut builder = SpanCollector::default();
+        if Parser::new_from_str(source).load(&mut builder, false).is_err() {
+            builder.spans.clear();
+        }
+
+        Self {
+            files: vec![SourceFile {
+                path,
+                lines: source.lines().map(str::to_string).collect(),
+            }],
+            spans: builder
+                .spans
+                .into_iter()
+                .map(|(path, span)| (path, (0, span)))
+                .collect(),
+        }
+    }
+
+    /// Finds the location of `path`, falling back to the closest ancestor that
+    /// exists in the source (e.g. the `with:` key for a missing parameter).
+    pub fn locate(&self, path: &str) -> Option<Location<'_>> {
+        let mut current = path;
+        loop {
+            if let Some((file, span)) = self.spans.get(current) {
+                return Some(Location {
+                    file: self.files[*file].path.as_deref(),
+                    span: *span,
+                });
+            }
+            let parent = current.rfind(['.', '['])?;
+            current = &current[..parent];
+        }
+    }
+
+    /// Renders the source line of `location` with a caret underline.
+    pub fn code_frame(&self, location: Location) -> String {
+        let span = location.span;
+        let Some(text) = self
+            .files
+            .iter()
+            .find(|f| f.path.as_deref() == location.file)
+            .and_then(|f| f.lines.get(span.line - 1))
+        else {
+            return String::new();
+        };
+        let gutter = span.line.to_string().len();
+        format!(
+            "{:gutter$} |\n{} | {}\n{:gutter$} | {}{}\n",
+            "",
+            span.line,
+            text,
+            "",
+            " ".repeat(span.column - 1),
+            "^".repeat(span.length.max(1)),
+            gutter = gutter,
+        )
+    }
+
+    /// Takes the nodes below `path` out of the map, re-rooted at the top.
+    pub(crate) fn split_off(&mut self, path: &str) -> SourceMap {
+        let mut taken = SourceMap {
+            files: Vec::new(),
+            spans: HashMap::new(),
+        };
+        let mut used = HashMap::new();
+        let below: Vec<String> = self
+            .spans
+            .keys()
+            .filter(|key| relative(key, path).is_some_and(|rest| !rest.is_empty()))
+            .cloned()
+            .collect();
+        for key in below {
+            let (file, span) = self.spans.remove(&key).expect("key was just listed");
+            let index = *used.entry(file).or_insert_with(|| {
+                taken.files.push(SourceFile {
+                    path: self.files[file].path.clone(),
+                    lines: self.files[file].lines.clone(),
+                });
+                taken.files.len() - 1
+            });
+            let rest = relative(&key, path).unwrap_or_default();
+            taken
+                .spans
+                .insert(rest.trim_start_matches('.').to_string(), (index, span));
+        }
+        taken
+    }
+
+    /// Adds the nodes of `other` below `path`, keeping the locations already
+    /// known for the same nodes.
+    pub(crate) fn insert_at(&mut self, path: &str, other: SourceMap) {
+        let offset = self.files.len();
+        self.files.extend(other.files);
+        for (key, (file, span)) in other.spans {
+            let key = if key.is_empty() {
+                path.to_string()
+            } else if path.is_empty() || key.starts_with('[') {
+                format!("{}{}", path, key)
+            } else {
+                format!("{}.{}", path, key)
+            };
+            self.spans.entry(key).or_insert((file + offset, span));
+        }
+    }
+
+    /// Renumbers the items of the list at `path`, e.g. once it is appended to
+    /// a list that already has `offset` items.
+    pub(crate) fn shift_items(&mut self, path: &str, offset: usize) {
+        let shifted: Vec<String> = self
+            .spans
+            .keys()
+            .filter(|key| relative(key, path).is_some_and(|rest| rest.starts_with('[')))
+            .cloned()
+            .collect();
+        let mut moved = Vec::new();
+        for key in shifted {
+            let rest = &key[path.len() + 1..];
+            let Some((index, tail)) = rest.split_once(']') else {
+                continue;
+            };
+            let Ok(index) = index.parse::<usize>() else {
+                continue;
+            };
+            let entry = self.spans.remove(&key).expect("key was just listed");
+            moved.push((format!("{}[{}]{}", path, index + offset, tail), entry));
+        }
+        self.spans.extend(moved);
+    }
+}
+
+/// The part of `key` after `path` (`.name`, `[0]...` or empty), when `key` is
+/// `path` or below it.
+fn relative<'a>(key: &'a str, path: &str) -> Option<&'a str> {
+    if path.is_empty() {
+        return Some(key);
+    }
+    let rest = key.strip_prefix(path)?;
+    (rest.is_empty() || rest.starts_with(['.', '['])).then_some(rest)
+}
+
+enum Frame {
+    Mapping {
+        path: String,
+        key: Option<(String, Span)>,
+    },
+    Sequence {
+        path: String,
+        index: usize,
+    },
+}
+
+#[derive(Default)]
+struct SpanCollector {
+    stack: Vec<Frame>,
+    spans: HashMap<String, Span>,
+}
+
+impl SpanCollector {
+    /// Consumes the next child slot of the innermost container and returns
+    /// its path and the span that should represent it.
+    fn next_child(&mut self, own_span: Span) -> Option<(String, Span)> {
+        match self.stack.last_mut()? {
+            Frame::Mapping { path, key } => {
+                let (name, key_span) = key.take()?;
+                Some((join(path, &name), key_span))
+            }
+            Frame::Sequence { path, index } => {
+                let child = format!("{}[{}]", path, index);
+                *index += 1;
+                Some((child, own_span))
+            }
+        }
+    }
+
+    fn push_container(&mut self, mark: Marker, sequence: bool) {
+        let own_span = span_at(mark, 1);
+        let path = match self.next_child(own_span) {
+            Some((path, span)) => {
+                self.spans.insert(path.clone(), span);
+                path
+            }
+            None => String::new(),
+        };
+
+        self.stack.push(if sequence {
+            Frame::Sequence { path, index: 0 }
+        } else {
+            Frame::Mapping { path, key: None }
+        });
+    }
+
+    fn scalar(&mut self, value: String, span: Span) {
+        if let Some(Frame::Mapping { key: key @ None, .. }) = self.stack.last_mut() {
+            *key = Some((value, span));
+            return;
+        }
+        if let Some((path, _)) = self.next_child(span) {
+            self.spans.insert(path, span);
+        }
+    }
+}
+
+impl MarkedEventReceiver for SpanCollector {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::MappingStart(..) => self.push_container(mark, false),
+            Event::SequenceStart(..) => self.push_container(mark, true),
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            Event::Scalar(value, style, ..) => {
+                let length = match style {
+                    TScalarStyle::Plain => value.chars().count(),
+                    TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => {
+                        value.chars().count() + 2
+                    }
+                    TScalarStyle::Literal | TScalarStyle::Folded => 1,
+                };
+                self.scalar(value, span_at(mark, length));
+            }
+            Event::Alias(_) => self.scalar(String::new(), span_at(mark, 1)),
+            _ => {}
+        }
+    }
+}
+
+fn span_at(mark: Marker, length: usize) -> Span {
+    Span {
+        line: mark.line(),
+        column: mark.col() + 1,
+        length,
+    }
+}
+
+fn join(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
@@ -0,0 +1,340 @@
+use serde_yaml_ng::{Mapping, Value};
+use std::fmt;
+
+/// The top-level key holding the substitution values.
+pub const SUBSTITUTIONS_KEY: &str = "substitutions";
+
+/// A `${...}` reference that has no value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndefinedSubstitution {
+    /// Configuration path of the scalar holding the reference
+    pub path: String,
+    /// The reference as written, e.g. `${led_pin}` or `${env:WIFI_SSID}`
+    pub reference: String,
+}
+
+impl fmt::Display for UndefinedSubstitution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reference.strip_prefix("${env:") {
+            Some(name) => write!(
+                f,
+                "Environment variable '{}' referenced by {} is not set",
+                name.trim_end_matches('}'),
+                self.reference
+            ),
+            None => write!(f, "Undefined substitution {}", self.reference),
+        }
+    }
+}
+
+/// Error for a configuration with undefined substitutions. Recover it with
+/// `error.downcast_ref::<UndefinedSubstitutions>()` to report each one at its
+/// location.
+#[derive(Debug, Clone)]
+pub struct UndefinedSubstitutions(pub Vec<UndefinedSubstitution>);
+
+impl fmt::Display for UndefinedSubstitutions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list: Vec<String> = self
+            .0
+            .iter()
+            .map(|u| format!("{} at {}", u.reference, u.path))
+            .collect();
+        write!(f, "Undefined substitution(s): {}", list.join(", "))
+    }
+}
+
+impl std::error::Error for UndefinedSubstitutions {}
+
+/// Replaces every `${name}` reference in the values of `document` with the
+/// entry of its top-level `substitutions:` map, and every `${env:NAME}` with
+/// the environment variable.
+///
+/// A scalar that is a single reference takes the substitution's type, so
+/// `pin: ${led_pin}` stays a number. Substitution values may themselves use
+/// `${env:NAME}`.
+pub fn expand(document: &mut Value) -> Result<(), UndefinedSubstitutions> {
+    let mut undefined = Vec::new();
+
+    let mut substitutions = match document.get(SUBSTITUTIONS_KEY) {
+        Some(Value::Mapping(map)) => map.clone(),
+        _ => Mapping::new(),
+    };
+    for (key, value) in substitutions.iter_mut() {
+        let path = format!("{}.{}", SUBSTITUTIONS_KEY, key_name(key));
+        expand_value(value, &path, &Mapping::new(), &mut undefined);
+    }
+
+    if let Value::Mapping(map) = document {
+        for (key, value) in map.iter_mut() {
+            let name = key_name(key);
+            if name == SUBSTITUTIONS_KEY {
+                *value = Value::Mapping(substitutions.clone());
+            } else {
+                expand_value(value, &name, &substitutions, &mut undefined);
+            }
+        }
+    }
+
+    if undefined.is_empty() {
+        Ok(())
+    } else {
+        Err(UndefinedSubstitutions(undefined))
+    }
+}
+
+/// Whether `document` defines substitutions or refers to one anywhere.
+pub fn is_used(document: &Value) -> bool {
+    document.get(SUBSTITUTIONS_KEY).is_some() || has_reference(document)
+}
+
+fn has_reference(value: &Value) -> bool {
+    match value {
+        Value::String(text) => text.contains("${"),
+        Value::Tagged(tagged) => has_reference(&tagged.value),
+        Value::Mapping(map) => map.iter().any(|(k, v)| has_reference(k) || has_reference(v)),
+        Value::Sequence(items) => items.iter().any(has_reference),
+        _ => false,
+    }
+}
+
+/// Sets the field at `path` to `value`. When the field is a single `${name}`
+/// reference to a substitution, the substitution is set instead, so that the
+/// other places using it follow (e.g. `--chip` with `platform: ${chip}`).
+pub fn set_field(document: &mut Value, path: &[&str], value: Value) {
+    let current = path
+        .iter()
+        .try_fold(&*document, |node, key| node.get(*key))
+        .and_then(Value::as_str)
+        .and_then(single_reference)
+        .filter(|name| !name.starts_with("env:"))
+        .map(str::to_string);
+
+    if let Some(name) = current
+        && let Some(Value::Mapping(substitutions)) = document.get_mut(SUBSTITUTIONS_KEY)
+        && substitutions.contains_key(name.as_str())
+    {
+        substitutions.insert(Value::String(name), value);
+        return;
+    }
+
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut node = document;
+    for key in parents {
+        let Value::Mapping(map) = node else {
+            return;
+        };
+        node = map
+            .entry(Value::String(key.to_string()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+    }
+    if let Value::Mapping(map) = node {
+        map.insert(Value::String(last.to_string()), value);
+    }
+}
+
+fn expand_value(
+    value: &mut Value,
+    path: &str,
+    substitutions: &Mapping,
+    undefined: &mut Vec<UndefinedSubstitution>,
+) {
+    match value {
+        Value::String(text) if text.contains("${") => {
+            if let Some(expanded) = expand_str(text, path, substitutions, undefined) {
+                *value = expanded;
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, child) in map.iter_mut() {
+                let child_path = format!("{}.{}", path, key_name(key));
+                expand_value(child, &child_path, substitutions, undefined);
+            }
+        }
+        Value::Sequence(items) => {
+            for (index, child) in items.iter_mut().enumerate() {
+                expand_value(
+                    child,
+                    &format!("{}[{}]", path, index),
+                    substitutions,
+                    undefined,
+                );
+            }
+        }
+        Value::Tagged(tagged) => expand_value(&mut tagged.value, path, substitutions, undefined),
+        _ => {}
+    }
+}
+
+/// The expanded scalar, or `None` when a reference is undefined.
+fn expand_str(
+    text: &str,
+    path: &str,
+    substitutions: &Mapping,
+    undefined: &mut Vec<UndefinedSubstitution>,
+) -> Option<Value> {
+    if let Some(name) = single_reference(text) {
+        let Some(value) = lookup(name, substitutions) else {
+            undefined.push(UndefinedSubstitution {
+                path: path.to_string(),
+                reference: text.to_string(),
+            });
+            return None;
+        };
+        return Some(value);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut complete = true;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let reference = &rest[start..start + len + 1];
+        out.push_str(&rest[..start]);
+        match lookup(&reference[2..reference.len() - 1], substitutions) {
+            Some(value) => out.push_str(&scalar_text(&value)),
+            None => {
+                undefined.push(UndefinedSubstitution {
+                    path: path.to_string(),
+                    reference: reference.to_string(),
+                });
+                complete = false;
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+
+    complete.then_some(Value::String(out))
+}
+
+/// `name` of a scalar that is exactly `${name}`.
+fn single_reference(text: &str) -> Option<&str> {
+    let name = text.strip_prefix("${")?.strip_suffix('}')?;
+    (!name.contains('}')).then_some(name)
+}
+
+fn lookup(name: &str, substitutions: &Mapping) -> Option<Value> {
+    match name.strip_prefix("env:") {
+        Some(variable) => std::env::var(variable).ok().map(|text| retype(&text)),
+        None => substitutions.get(name.trim()).cloned(),
+    }
+}
+
+/// Environment variables are strings; read them as a YAML scalar so that
+/// `pin: ${env:LED_PIN}` is still a number.
+fn retype(text: &str) -> Value {
+    match serde_yaml_ng::from_str::<Value>(text) {
+        Ok(value @ (Value::Bool(_) | Value::Number(_))) => value,
+        _ => Value::String(text.to_string()),
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => serde_yaml_ng::to_string(other)
+            .map(|text| text.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(text) => text.clone(),
+        other => scalar_text(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Value {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    fn expanded(yaml: &str) -> Result<Value, UndefinedSubstitutions> {
+        let mut document = parse(yaml);
+        expand(&mut document).map(|()| document)
+    }
+
+    #[test]
+    fn undefined_references_are_all_reported() {
+        let error = expanded(
+            "substitutions:\n  chip: esp32c3\nespforge:\n  name: ${name}\n  platform: ${chip}\napp:\n  setup:\n    - log.info: \"pin ${led_pin} of ${board}\"\n",
+        )
+        .unwrap_err();
+
+        let found: Vec<(&str, &str)> = error
+            .0
+            .iter()
+            .map(|u| (u.path.as_str(), u.reference.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("espforge.name", "${name}"),
+                ("app.setup[0].log.info", "${led_pin}"),
+                ("app.setup[0].log.info", "${board}"),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Undefined substitution(s): ${name} at espforge.name, ${led_pin} at app.setup[0].log.info, ${board} at app.setup[0].log.info"
+        );
+    }
+
+    #[test]
+    fn unset_environment_variables_are_undefined() {
+        let error = expanded(
+            "substitutions:\n  ssid: ${env:ESPFORGE_TEST_UNSET_VARIABLE}\nespforge:\n  name: blink\n",
+        )
+        .unwrap_err();
+
+        assert_eq!(error.0.len(), 1);
+        assert_eq!(error.0[0].path, "substitutions.ssid");
+        assert_eq!(
+            error.0[0].to_string(),
+            "Environment variable 'ESPFORGE_TEST_UNSET_VARIABLE' referenced by ${env:ESPFORGE_TEST_UNSET_VARIABLE} is not set"
+        );
+    }
+
+    #[test]
+    fn whole_scalars_keep_their_type_and_partial_ones_become_text() {
+        let document = expanded(
+            "substitutions:\n  pin: 8\n  enabled: true\nesp32:\n  gpio:\n    led: { pin: \"${pin}\", pullup: \"${enabled}\" }\napp:\n  setup:\n    - log.info: \"led on ${pin}, enabled: ${enabled}\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(document["esp32"]["gpio"]["led"]["pin"], Value::from(8));
+        assert_eq!(document["esp32"]["gpio"]["led"]["pullup"], Value::Bool(true));
+        assert_eq!(
+            document["app"]["setup"][0]["log.info"],
+            Value::from("led on 8, enabled: true")
+        );
+    }
+
+    #[test]
+    fn set_field_overrides_the_substitution_a_field_refers_to() {
+        let mut document = parse(
+            "substitutions:\n  chip: esp32c3\nespforge:\n  name: blink_${chip}\n  platform: ${chip}\n",
+        );
+        set_field(&mut document, &["espforge", "platform"], Value::from("esp32s3"));
+        expand(&mut document).unwrap();
+
+        assert_eq!(document["substitutions"]["chip"], Value::from("esp32s3"));
+        assert_eq!(document["espforge"]["platform"], Value::from("esp32s3"));
+        assert_eq!(document["espforge"]["name"], Value::from("blink_esp32s3"));
+
+        // A plain field, or one missing, is set directly
+        let mut document = parse("espforge:\n  name: blink\n");
+        set_field(&mut document, &["espforge", "platform"], Value::from("esp32s3"));
+        assert_eq!(document["espforge"]["platform"], Value::from("esp32s3"));
+    }
+}
@@ -1,8 +1,9 @@
 use crate::components::spi::SPI;
+use crate::platform::spi::SPIMaster;
 //use crate::platform::gpio::GPIOOutput;
 use esp_hal::gpio::{AnyPin, Output, OutputConfig, Level};
 use esp_hal::delay::Delay;
-use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
+use embedded_hal_bus::spi::{NoDelay, RefCellDevice};
 use mipidsi::{Builder, models::ILI9341Rgb565, options::Orientation, interface::SpiInterface};
 use embedded_graphics::{
     mono_font::{ascii::FONT_6X10, MonoTextStyle, MonoTextStyleBuilder},
@@ -17,11 +18,7 @@ pub struct ILI9341Device<'a> {
     // In a production embedded env, we might typedef this instead of boxing, but this is safer for generation.
     display: mipidsi::Display<
         SpiInterface<'a, 
-            ExclusiveDevice<
-                esp_hal::spi::master::Spi<'static, esp_hal::Blocking>, 
-                Output<'static>, 
-                NoDelay
-            >, 
+            RefCellDevice<'a, SPIMaster, Output<'static>, NoDelay>, 
             Output<'static>
         >, 
         ILI9341Rgb565, 
@@ -32,29 +29,23 @@ pub struct ILI9341Device<'a> {
 
 impl<'a> ILI9341Device<'a> {
     // CHANGE: Added buffer argument with lifetime 'a
-    pub fn new(spi_component: SPI, cs_pin: u8, dc_pin: u8, rst_pin: u8, buffer: &'a mut [u8]) -> Self {
-        // 1. Unwrap the SPI bus from the generic component
-        let spi_hal = spi_component.into_inner().into_inner();
+    pub fn new(spi_component: &'a SPI, cs_pin: u8, dc_pin: u8, rst_pin: u8, buffer: &'a mut [u8]) -> Self {
+        // 1. Borrow a device on the shared SPI bus, selected by our own CS pin
+        let spi_device = spi_component.device(cs_pin);
 
         // 2. Setup GPIOs manually using AnyPin steal (standard espforge pattern)
         // We use Output from esp_hal directly because mipidsi expects embedded-hal outputs
-        let cs_any = unsafe { AnyPin::steal(cs_pin) };
-        let cs = Output::new(cs_any, Level::High, OutputConfig::default());
-
         let dc_any = unsafe { AnyPin::steal(dc_pin) };
         let dc = Output::new(dc_any, Level::Low, OutputConfig::default());
 
         let rst_any = unsafe { AnyPin::steal(rst_pin) };
         let rst = Output::new(rst_any, Level::Low, OutputConfig::default());
 
-        // 3. Create Exclusive Device (Software CS control)
-        let spi_device = ExclusiveDevice::new_no_delay(spi_hal, cs).unwrap();
-
-        // 4. Create Interface
+        // 3. Create Interface
         // CHANGE: Use the passed-in buffer
         let di = SpiInterface::new(spi_device, dc, buffer);
 
-        // 5. Init Display
+        // 4. Init Display
         // Note: We perform the builder init here. In the generated main, .init() is called, 
         // but for mipidsi the builder pattern initializes during construction of the struct.
         // We can keep a separate init() if we want to delay turning it on, but standard builder does it now.
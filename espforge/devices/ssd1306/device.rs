@@ -1,4 +1,6 @@
 use crate::components::i2c::I2C; // Import the component type
+use crate::platform::i2c::I2CMaster;
+use embedded_hal_bus::i2c::RefCellDevice;
 use ssd1306::{prelude::*, I2CDisplayInterface, Ssd1306, mode::BufferedGraphicsMode};
 use display_interface_i2c::I2CInterface;
 use embedded_graphics::{
@@ -9,27 +11,23 @@ use embedded_graphics::{
 };
 
 // We wrap the complex generic type into a concrete struct for the main app to use
-pub struct SSD1306Device {
+pub struct SSD1306Device<'a> {
     // Ssd1306<I2CInterface<I2c<I2C0, Blocking>>, DisplaySize128x64, BufferedGraphicsMode<DisplaySize128x64>>
     // For simplicity in this example, we use a dynamic dispatch or simplified type expectation.
     // In a real scenario, you might box this or make the wrapper generic.
     display: Ssd1306<
-        I2CInterface<crate::platform::i2c::I2CMaster>, 
+        I2CInterface<RefCellDevice<'a, I2CMaster>>, 
         DisplaySize128x64, 
         BufferedGraphicsMode<DisplaySize128x64>
     >,
     text_style: embedded_graphics::mono_font::MonoTextStyle<'static, BinaryColor>,
 }
 
-impl SSD1306Device {
-    /// The constructor takes the I2C Component struct.
-    /// Note: This consumes the I2C component instance.
-    pub fn new(i2c_component: I2C) -> Self {
-        // We assume the I2C component exposes its inner HAL driver via .into_inner()
-        // or implements the embedded-hal traits directly.
-        let i2c_hal = i2c_component.into_inner(); 
-
-        let interface = I2CDisplayInterface::new(i2c_hal);
+impl<'a> SSD1306Device<'a> {
+    /// The constructor borrows the I2C Component struct, so other devices can
+    /// share the bus. `address` is the 7-bit display address (usually 0x3C).
+    pub fn new(i2c_component: &'a I2C, address: u8) -> Self {
+        let interface = I2CDisplayInterface::new_custom_address(i2c_component.device(), address);
         
         // Initialize in Buffered Mode
         let display = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
@@ -1,7 +1,12 @@
-use crate::config::{EspforgeConfiguration, PlatformConfig};
+use crate::config::{EspforgeConfiguration, PlatformConfig, substitutions};
+use crate::metadata::load_chip_metadata;
+use crate::pack;
 use crate::template_utils::{copy_verbatim, find_template_path, get_templates, write_template};
 use anyhow::{Context, Result, anyhow};
-use std::path::Path;
+use include_dir::Dir;
+use serde_yaml_ng::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub struct ExportOptions {
     pub example_name: String,
@@ -11,83 +16,165 @@ pub struct ExportOptions {
 
 pub fn export_example(options: ExportOptions, target_dir: &Path) -> Result<String> {
     let root = get_templates();
+    let (example_dir, raw_yaml) = read_example(&options.example_name)?;
 
-    let template_path_str = find_template_path(&options.example_name)
-        .ok_or_else(|| anyhow!("Example '{}' not found", options.example_name))?;
-
-    let template_dir = root.get_dir(&template_path_str).ok_or_else(|| {
-        anyhow!(
-            "Template directory structure error for '{}'",
-            options.example_name
-        )
-    })?;
-
-    let example_file = template_dir.path().join("example.yaml");
-    // Use root to get file by full path to ensure correct lookup
-    let yaml_file = root
-        .get_file(&example_file)
-        .ok_or_else(|| anyhow!("Template is missing example.yaml"))?;
-
-    let raw_yaml = yaml_file
-        .contents_utf8()
-        .context("Invalid UTF-8 in example.yaml")?;
-
-    let mut config: EspforgeConfiguration = serde_yaml_ng::from_str(raw_yaml)
-        .context("Failed to parse example.yaml into configuration")?;
-
+    // Overrides go into the document as written, so `${...}` references are
+    // kept; a field that is a reference has its substitution set instead
+    let mut document: Value =
+        serde_yaml_ng::from_str(&raw_yaml).context("Failed to parse example.yaml")?;
     if let Some(name) = options.override_project_name {
-        config.espforge.name = name;
+        substitutions::set_field(&mut document, &["espforge", "name"], Value::String(name));
     }
 
     if let Some(platform_str) = options.override_platform {
-        let platform_enum: PlatformConfig = serde_yaml_ng::from_str(&platform_str)
-            .map_err(|_| anyhow!("Invalid platform: {}", platform_str))?;
-        config.espforge.platform = platform_enum;
+        let platform = platform_str.parse::<PlatformConfig>()?;
+        substitutions::set_field(
+            &mut document,
+            &["espforge", "platform"],
+            Value::String(platform.to_string()),
+        );
     }
 
+    let modified_yaml = serde_yaml_ng::to_string(&document)?;
+    let config = example_config(&document)?;
     let project_name = config.get_name().to_string();
 
     let yaml_filename = format!("{}.yaml", project_name);
     let yaml_dest = target_dir.join(&yaml_filename);
 
-    let modified_yaml = serde_yaml_ng::to_string(&config)?;
     write_template(&yaml_dest, &modified_yaml)?;
     println!("Created config: {}", yaml_filename);
 
     let project_path_str = target_dir.to_string_lossy();
-    let template_root_path = template_dir.path();
-
-    for entry in template_dir.find("**/*")? {
-        if let Some(file) = entry.as_file() {
-            let file_path = file.path();
-            let file_name = file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            if file_name == "example.yaml" {
-                continue;
-            }
 
-            if file_path
-                .components()
-                .any(|c| c.as_os_str() == ".zig-cache" || c.as_os_str() == "zig-out")
-            {
-                continue;
+    match example_dir {
+        ExampleDir::Embedded(template_dir) => {
+            let template_root_path = template_dir.path();
+
+            for entry in template_dir.find("**/*")? {
+                if let Some(file) = entry.as_file() {
+                    let file_path = file.path();
+                    if is_skipped(file_path) {
+                        continue;
+                    }
+
+                    // We do not render templates here; that happens during 'compile'.
+                    copy_verbatim(file, template_root_path, &project_path_str)?;
+                    let relative_path = file_path
+                        .strip_prefix(template_root_path)
+                        .unwrap_or(file_path);
+                    println!("Created file: {}", relative_path.display());
+                }
             }
+        }
+        ExampleDir::Pack(template_dir) => copy_pack_example(&template_dir, &template_dir, target_dir)?,
+    }
+
+    let target_cargo_tera = target_dir.join("Cargo.toml.tera");
+    if !target_cargo_tera.exists()
+        && let Some(dynamic_cargo) = root.get_file("_dynamic/Cargo.toml.tera")
+    {
+        copy_verbatim(dynamic_cargo, Path::new("_dynamic"), &project_path_str)?;
+        println!("Created file: Cargo.toml.tera (from _dynamic)");
+    }
+
+    Ok(project_name)
+}
+
+/// Where an example's files come from.
+enum ExampleDir {
+    Embedded(&'static Dir<'static>),
+    /// An example of an installed pack
+    Pack(PathBuf),
+}
 
-            // We do not render templates here; that happens during 'compile'.
-            copy_verbatim(file, template_root_path, &project_path_str)?;
-            let relative_path = file_path
-                .strip_prefix(template_root_path)
-                .unwrap_or(file_path);
+/// `example.yaml` is written separately; build output of wokwi chips is not copied.
+fn is_skipped(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n == "example.yaml")
+        || path
+            .components()
+            .any(|c| c.as_os_str() == ".zig-cache" || c.as_os_str() == "zig-out")
+}
+
+fn copy_pack_example(root: &Path, dir: &Path, target_dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let relative_path = path.strip_prefix(root).unwrap_or(&path);
+        if is_skipped(relative_path) {
+            continue;
+        }
+        if path.is_dir() {
+            copy_pack_example(root, &path, target_dir)?;
+        } else {
+            let dest = target_dir.join(relative_path);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&path, &dest).with_context(|| format!("Failed to copy {}", path.display()))?;
             println!("Created file: {}", relative_path.display());
         }
     }
+    Ok(())
+}
 
-    let target_cargo_tera = target_dir.join("Cargo.toml.tera");
-    if !target_cargo_tera.exists() {
-        if let Some(dynamic_cargo) = root.get_file("_dynamic/Cargo.toml.tera") {
-             copy_verbatim(dynamic_cargo, Path::new("_dynamic"), &project_path_str)?;
-             println!("Created file: Cargo.toml.tera (from _dynamic)");
+/// Chips that have every pin and bus the example's `esp32:` section uses.
+pub fn supported_platforms(example_name: &str) -> Result<Vec<PlatformConfig>> {
+    let (_, raw_yaml) = read_example(example_name)?;
+    let document: Value =
+        serde_yaml_ng::from_str(&raw_yaml).context("Failed to parse example.yaml")?;
+    let config = example_config(&document)?;
+
+    let mut platforms = Vec::new();
+    for platform in PlatformConfig::ALL {
+        let supported = match &config.esp32 {
+            Some(esp32) => load_chip_metadata(platform)?.supports(esp32),
+            None => true,
+        };
+        if supported {
+            platforms.push(platform);
         }
     }
+    Ok(platforms)
+}
 
-    Ok(project_name)
-}
\ No newline at end of file
+/// Parses an example's configuration. Undefined references (typically
+/// `${env:...}`) are left as written: they only need a value at compile time.
+fn example_config(document: &Value) -> Result<EspforgeConfiguration> {
+    let mut expanded = document.clone();
+    let _ = substitutions::expand(&mut expanded);
+    serde_yaml_ng::from_value(expanded).context("Failed to parse example.yaml into configuration")
+}
+
+/// Finds an embedded or pack example and reads its `example.yaml`.
+fn read_example(example_name: &str) -> Result<(ExampleDir, String)> {
+    let root = get_templates();
+
+    let (example_dir, raw_yaml) = match find_template_path(example_name) {
+        Some(template_path_str) => {
+            let template_dir = root.get_dir(&template_path_str).ok_or_else(|| {
+                anyhow!("Template directory structure error for '{}'", example_name)
+            })?;
+
+            let example_file = template_dir.path().join("example.yaml");
+            // Use root to get file by full path to ensure correct lookup
+            let yaml_file = root
+                .get_file(&example_file)
+                .ok_or_else(|| anyhow!("Template is missing example.yaml"))?;
+
+            let raw_yaml = yaml_file
+                .contents_utf8()
+                .context("Invalid UTF-8 in example.yaml")?;
+            (ExampleDir::Embedded(template_dir), raw_yaml.to_string())
+        }
+        None => {
+            let dir = pack::find_example(example_name)?
+                .ok_or_else(|| anyhow!("Example '{}' not found", example_name))?;
+            let example_file = dir.join("example.yaml");
+            let raw_yaml = fs::read_to_string(&example_file)
+                .with_context(|| format!("Failed to read {}", example_file.display()))?;
+            (ExampleDir::Pack(dir), raw_yaml)
+        }
+    };
+
+    Ok((example_dir, raw_yaml))
+}
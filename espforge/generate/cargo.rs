@@ -1,6 +1,11 @@
 use super::manifest;
+use super::output::ProjectOutput;
+use crate::config::{GeneratorOption, PlatformConfig};
+use crate::manifest::ComponentManifest;
 use anyhow::{Context, Result};
 use log::{debug, info, warn};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::fs;
 use std::path::Path;
 use toml_edit::{Array, DocumentMut, Item, Table, Value};
@@ -8,14 +13,15 @@ use toml_edit::{Array, DocumentMut, Item, Table, Value};
 const CARGO_TEMPLATE_NAME: &str = "Cargo.toml.tera";
 
 pub fn update_manifest(
-    project_path: &Path, 
+    output: &mut ProjectOutput,
     config_dir: &Path, 
     chip: &str,
-    context: &tera::Context
+    context: &tera::Context,
+    modules: &BTreeSet<String>,
+    manifests: &HashMap<String, ComponentManifest>,
+    options: &[GeneratorOption],
 ) -> Result<()> {
-    let cargo_path = project_path.join("Cargo.toml");
-    let cargo_content = fs::read_to_string(&cargo_path)
-        .with_context(|| format!("Failed to read {}", cargo_path.display()))?;
+    let cargo_content = output.read_to_string("Cargo.toml")?;
     
     let mut doc = cargo_content.parse::<DocumentMut>()
         .context("Failed to parse Cargo.toml")?;
@@ -46,18 +52,112 @@ pub fn update_manifest(
         merge_documents(&mut doc, &user_doc);
     }
 
-    // 3. System updates
-    add_chip_features(&mut doc, chip)?;
+    // 3. System updates; features left by an earlier chip or logging choice go
+    for platform in PlatformConfig::ALL {
+        let name = platform.to_string();
+        if name != chip {
+            remove_default_feature(&mut doc, &name)?;
+        }
+    }
+    add_default_feature(&mut doc, chip)?;
+    // Lets the logging global pick defmt over `log`
+    if options.contains(&GeneratorOption::Defmt) {
+        add_default_feature(&mut doc, "log-defmt")?;
+    } else {
+        remove_default_feature(&mut doc, "log-defmt")?;
+    }
     ensure_workspace_exists(&mut doc);
-    merge_device_dependencies(&mut doc)?;
+    merge_device_dependencies(&mut doc, modules, manifests)?;
 
-    fs::write(&cargo_path, doc.to_string())
-        .with_context(|| format!("Failed to write {}", cargo_path.display()))?;
+    // Merged into the existing manifest, so local edits are kept
+    output.add_merged("Cargo.toml", doc.to_string());
 
     info!("Updated Cargo.toml");
     Ok(())
 }
 
+/// The chip and generator options a project skeleton was generated for.
+///
+/// Recorded under `[package.metadata.espforge]`, since the skeleton files
+/// (`Cargo.toml`, `.cargo/config.toml`, `rust-toolchain.toml`, ...) are only
+/// written when the project is created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkeletonInfo {
+    pub platform: String,
+    pub options: Vec<String>,
+}
+
+impl SkeletonInfo {
+    pub fn new(platform: PlatformConfig, options: &[GeneratorOption]) -> Self {
+        Self {
+            platform: platform.to_string(),
+            options: options.iter().map(|o| o.as_str().to_string()).collect(),
+        }
+    }
+
+    /// Reads the record from a project's `Cargo.toml`. `None` for projects
+    /// generated before it was kept.
+    pub fn recorded(cargo_content: &str) -> Option<Self> {
+        let doc = cargo_content.parse::<DocumentMut>().ok()?;
+        let espforge = doc.get("package")?.get("metadata")?.get("espforge")?;
+        Some(Self {
+            platform: espforge.get("platform")?.as_str()?.to_string(),
+            options: espforge
+                .get("options")?
+                .as_array()?
+                .iter()
+                .filter_map(|o| o.as_str().map(str::to_string))
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for SkeletonInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}]", self.platform, self.options.join(", "))
+    }
+}
+
+/// Records the skeleton and the profile the project was compiled with under
+/// `[package.metadata.espforge]`; the profile is removed when there is none.
+pub fn record_metadata(
+    output: &mut ProjectOutput,
+    skeleton: &SkeletonInfo,
+    profile: Option<&str>,
+) -> Result<()> {
+    let cargo_content = output.read_to_string("Cargo.toml")?;
+    let mut doc = cargo_content.parse::<DocumentMut>()
+        .context("Failed to parse Cargo.toml")?;
+
+    let package = doc["package"]
+        .as_table_mut()
+        .context("Cargo.toml has no [package] table")?;
+    package.set_implicit(false);
+    let metadata = package
+        .entry("metadata")
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .context("'package.metadata' is not a table")?;
+    metadata.set_implicit(true);
+    let espforge = metadata
+        .entry("espforge")
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .context("'package.metadata.espforge' is not a table")?;
+
+    espforge["platform"] = toml_edit::value(&skeleton.platform);
+    espforge["options"] = toml_edit::value(skeleton.options.iter().collect::<Array>());
+    match profile {
+        Some(profile) => espforge["profile"] = toml_edit::value(profile),
+        None => {
+            espforge.remove("profile");
+        }
+    }
+
+    output.add_merged("Cargo.toml", doc.to_string());
+    Ok(())
+}
+
 fn merge_documents(target: &mut DocumentMut, source: &DocumentMut) {
     // FIX: Added "features" to the list so it gets copied from the template
     for section in ["dependencies", "build-dependencies", "dev-dependencies", "features"] {
@@ -77,7 +177,7 @@ fn merge_documents(target: &mut DocumentMut, source: &DocumentMut) {
     }
 }
 
-fn add_chip_features(doc: &mut DocumentMut, chip: &str) -> Result<()> {
+fn add_default_feature(doc: &mut DocumentMut, feature: &str) -> Result<()> {
     if !doc.contains_key("features") {
         doc["features"] = Item::Table(Table::new());
     }
@@ -99,17 +199,42 @@ fn add_chip_features(doc: &mut DocumentMut, chip: &str) -> Result<()> {
         .as_array_mut()
         .context("'default' feature is not an array")?;
 
-    // Add chip to default if not present
-    let exists = default_array.iter().any(|v| v.as_str() == Some(chip));
+    // Add feature to default if not present
+    let exists = default_array.iter().any(|v| v.as_str() == Some(feature));
     if !exists {
-        default_array.push(chip);
+        default_array.push(feature);
     }
 
-    if !features.contains_key(chip) {
-        features[chip] = Item::Value(Value::Array(Array::new()));
+    if !features.contains_key(feature) {
+        features[feature] = Item::Value(Value::Array(Array::new()));
     }
     
-    debug!("Added feature for chip: {}", chip);
+    debug!("Added default feature: {}", feature);
+    Ok(())
+}
+
+fn remove_default_feature(doc: &mut DocumentMut, feature: &str) -> Result<()> {
+    let Some(features) = doc.get_mut("features").and_then(Item::as_table_mut) else {
+        return Ok(());
+    };
+
+    if let Some(default_array) = features
+        .get_mut("default")
+        .and_then(Item::as_value_mut)
+        .and_then(Value::as_array_mut)
+    {
+        default_array.retain(|v| v.as_str() != Some(feature));
+    }
+
+    // Only the empty placeholder add_default_feature creates
+    let placeholder = features
+        .get(feature)
+        .and_then(Item::as_array)
+        .is_some_and(Array::is_empty);
+    if placeholder {
+        features.remove(feature);
+        debug!("Removed default feature: {}", feature);
+    }
     Ok(())
 }
 
@@ -119,7 +244,15 @@ fn ensure_workspace_exists(doc: &mut DocumentMut) {
     }
 }
 
-fn merge_device_dependencies(doc: &mut DocumentMut) -> Result<()> {
+/// Merges the `Cargo.toml.tera` dependencies of the devices in `modules` only,
+/// reading local devices' templates from next to their manifest. Embedded
+/// components and platform modules declare theirs in a `<name>.Cargo.toml.tera`
+/// next to their code.
+fn merge_device_dependencies(
+    doc: &mut DocumentMut,
+    modules: &BTreeSet<String>,
+    manifests: &HashMap<String, ComponentManifest>,
+) -> Result<()> {
     if !doc.contains_key("dependencies") {
         doc["dependencies"] = Item::Table(Table::new());
     }
@@ -128,25 +261,67 @@ fn merge_device_dependencies(doc: &mut DocumentMut) -> Result<()> {
         .as_table_mut()
         .context("'dependencies' is not a table")?;
 
+    let mut templates = Vec::new();
+    let local: HashMap<&str, &Path> = manifests
+        .values()
+        .filter(|m| modules.contains(&m.module))
+        .filter_map(|m| Some((m.module.strip_prefix("devices/")?, m.source.as_deref()?.parent()?)))
+        .collect();
+    for (device_name, dir) in &local {
+        let path = dir.join(CARGO_TEMPLATE_NAME);
+        if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            templates.push((device_name.to_string(), content));
+        }
+    }
+
     for subdir in manifest::devices_dir().dirs() {
+        let device_name = subdir.path().file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if !modules.contains(&format!("devices/{}", device_name)) || local.contains_key(device_name) {
+            continue;
+        }
+
         if let Some(cargo_file) = find_cargo_template(subdir) {
-            let device_toml_str = cargo_file
+            let content = cargo_file
                 .contents_utf8()
                 .context("Device Cargo.toml.tera is not valid UTF-8")?;
+            templates.push((device_name.to_string(), content.to_string()));
+        }
+    }
+    for (prefix, dir) in [
+        ("components", manifest::components_dir()),
+        ("platform", manifest::platform_dir()),
+    ] {
+        let suffix = format!(".{}", CARGO_TEMPLATE_NAME);
+        for file in dir.files() {
+            let Some(name) = file.path().to_str().and_then(|p| p.strip_suffix(&suffix)) else {
+                continue;
+            };
+            let module = format!("{}/{}", prefix, name);
+            if modules.contains(&module) {
+                let content = file
+                    .contents_utf8()
+                    .with_context(|| format!("{}{} is not valid UTF-8", module, suffix))?;
+                templates.push((module, content.to_string()));
+            }
+        }
+    }
+    templates.sort();
+
+    for (device_name, device_toml_str) in templates {
+        let device_doc = device_toml_str.parse::<DocumentMut>()
+            .with_context(|| {
+                format!("Failed to parse Cargo.toml.tera for {}", device_name)
+            })?;
 
-            let device_doc = device_toml_str.parse::<DocumentMut>()
-                .with_context(|| {
-                    format!("Failed to parse Cargo.toml.tera for device {:?}", subdir.path())
-                })?;
-
-            if let Some(deps) = device_doc.get("dependencies").and_then(|d| d.as_table()) {
-                for (key, value) in deps.iter() {
-                    if root_deps.contains_key(key) {
-                        warn!("Dependency '{}' already exists, skipping from device", key);
-                    } else {
-                        root_deps.insert(key, value.clone());
-                        debug!("Added dependency: {}", key);
-                    }
+        if let Some(deps) = device_doc.get("dependencies").and_then(|d| d.as_table()) {
+            for (key, value) in deps.iter() {
+                if root_deps.contains_key(key) {
+                    warn!("Dependency '{}' already exists, skipping from device", key);
+                } else {
+                    root_deps.insert(key, value.clone());
+                    debug!("Added dependency: {}", key);
                 }
             }
         }
@@ -1,39 +1,50 @@
+use super::output::ProjectOutput;
+use crate::config::GeneratorOption;
 use anyhow::{Context, Result};
 use log::debug;
+use std::fs;
 use std::process::Command;
 
-pub fn run(project_name: &str, chip: &str, enable_async: bool) -> Result<()> {
+/// Runs esp-generate in a scratch directory and stages the skeleton it creates,
+/// so nothing reaches the project folder before [`ProjectOutput::write`].
+pub fn run(
+    output: &mut ProjectOutput,
+    project_name: &str,
+    chip: &str,
+    options: &[GeneratorOption],
+) -> Result<()> {
     debug!("Running esp-generate for chip: {}", chip);
-    
+
+    let scratch = std::env::temp_dir().join(format!("espforge-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&scratch);
+    fs::create_dir_all(&scratch)
+        .with_context(|| format!("Failed to create directory: {}", scratch.display()))?;
+
     let mut cmd = Command::new("esp-generate");
-    cmd.arg("--headless")
+    cmd.current_dir(&scratch)
+        .arg("--headless")
         .arg("--chip")
-        .arg(chip)
-        .arg("-o")
-        .arg("log")
-        .arg("-o")
-        .arg("unstable-hal")
-        .arg("-o")
-        .arg("esp-backtrace")
-        .arg("-o")
-        .arg("wokwi")
-        .arg("-o")
-        .arg("vscode");
-    
-    if enable_async {
-        debug!("Enabling embassy async support");
-        cmd.arg("-o").arg("embassy");
+        .arg(chip);
+
+    for option in options {
+        debug!("Enabling esp-generate option: {}", option);
+        cmd.arg("-o").arg(option.as_str());
     }
 
-    let output = cmd.arg(project_name).output()
-        .context("Failed to execute esp-generate command")?;
+    let result = cmd
+        .arg(project_name)
+        .output()
+        .context("Failed to execute esp-generate command")
+        .and_then(|out| {
+            if !out.status.success() {
+                anyhow::bail!(
+                    "esp-generate failed: {}",
+                    String::from_utf8_lossy(&out.stderr)
+                );
+            }
+            output.add_dir(&scratch.join(project_name))
+        });
 
-    if !output.status.success() {
-        anyhow::bail!(
-            "esp-generate failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-    }
-    
-    Ok(())
-}
\ No newline at end of file
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
@@ -1,29 +1,98 @@
+use crate::config::EspforgeConfiguration;
 use crate::manifest::ComponentManifest;
+use crate::pack;
 use anyhow::{Context, Result};
 use include_dir::{Dir, include_dir};
 use log::{debug, info};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 static COMPONENTS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/components");
 static GLOBALS_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/globals");
 static DEVICES_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/devices");
+static PLATFORM_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/platform");
+
+/// The embedded directories a manifest can be loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestKind {
+    Component,
+    Global,
+    Device,
+}
+
+impl ManifestKind {
+    pub const ALL: [ManifestKind; 3] = [
+        ManifestKind::Component,
+        ManifestKind::Global,
+        ManifestKind::Device,
+    ];
 
+    fn dir(&self) -> &'static Dir<'static> {
+        match self {
+            ManifestKind::Component => &COMPONENTS_DIR,
+            ManifestKind::Global => &GLOBALS_DIR,
+            ManifestKind::Device => &DEVICES_DIR,
+        }
+    }
+
+    /// Directory of the generated crate's `src/` the modules are copied to.
+    fn module_dir(&self) -> &'static str {
+        match self {
+            ManifestKind::Component => "components",
+            ManifestKind::Global => "globals",
+            ManifestKind::Device => "devices",
+        }
+    }
+
+    /// The kind of a loaded manifest, from the module it belongs to.
+    pub fn of(manifest: &ComponentManifest) -> Option<ManifestKind> {
+        let dir = manifest.module.split('/').next()?;
+        ManifestKind::ALL.into_iter().find(|k| k.module_dir() == dir)
+    }
+}
+
+/// The embedded manifests, merged with those of the installed packs (see
+/// [`crate::pack`]). A pack manifest replaces an embedded one of the same
+/// name or module.
 pub fn load_manifests() -> Result<HashMap<String, ComponentManifest>> {
     let mut manifests = HashMap::new();
 
-    load_from_dir(&COMPONENTS_DIR, &mut manifests)?;
-    load_from_dir(&GLOBALS_DIR, &mut manifests)?;
-    load_from_dir(&DEVICES_DIR, &mut manifests)?;
+    for kind in ManifestKind::ALL {
+        load_from_dir(kind, &mut manifests)?;
+    }
+
+    let packs = pack::active_packs().unwrap_or_else(|e| {
+        eprintln!("Warning: failed to load packs: {:#}", e);
+        Vec::new()
+    });
+    for pack in packs {
+        match load_root_manifests(&pack.root) {
+            Ok(pack_manifests) => {
+                for manifest in pack_manifests {
+                    insert_replacing(&mut manifests, manifest);
+                }
+            }
+            Err(e) => eprintln!("Warning: skipping pack '{}': {:#}", pack.metadata.name, e),
+        }
+    }
     
     info!("Loaded {} component manifests", manifests.len());
     Ok(manifests)
 }
 
+/// Loads only the manifests of a single kind (e.g. just the globals).
+pub fn load_manifests_of(kind: ManifestKind) -> Result<HashMap<String, ComponentManifest>> {
+    let mut manifests = HashMap::new();
+    load_from_dir(kind, &mut manifests)?;
+    Ok(manifests)
+}
+
 fn load_from_dir(
-    dir: &Dir<'_>, 
+    kind: ManifestKind,
     manifests: &mut HashMap<String, ComponentManifest>
 ) -> Result<()> {
-    for entry in dir.find("**/*.ron")? {
+    for entry in kind.dir().find("**/*.ron")? {
         if let Some(file) = entry.as_file() {
             let path_str = file.path().to_str().context("Invalid UTF-8 in file path")?;
 
@@ -31,8 +100,9 @@ fn load_from_dir(
                 .contents_utf8()
                 .with_context(|| format!("File {} is not valid UTF-8", path_str))?;
 
-            let manifest: ComponentManifest = ron::from_str(content)
+            let mut manifest: ComponentManifest = ron::from_str(content)
                 .with_context(|| format!("Failed to parse manifest file: {}", path_str))?;
+            manifest.module = module_of(kind, file.path());
             
             debug!("Loaded manifest: {}", manifest.name);
             manifests.insert(manifest.name.clone(), manifest);
@@ -41,6 +111,139 @@ fn load_from_dir(
     Ok(())
 }
 
+/// The manifests of [`load_manifests`], with the local ones of `config`
+/// replacing any of the same name or module.
+pub fn load_config_manifests(
+    config: &EspforgeConfiguration,
+) -> Result<HashMap<String, ComponentManifest>> {
+    let mut manifests = load_manifests()?;
+
+    for local in load_local_manifests(config)? {
+        insert_replacing(&mut manifests, local);
+    }
+    Ok(manifests)
+}
+
+/// Inserts `manifest`, dropping any other manifest of the same module.
+fn insert_replacing(manifests: &mut HashMap<String, ComponentManifest>, manifest: ComponentManifest) {
+    manifests.retain(|_, m| m.module != manifest.module);
+    manifests.insert(manifest.name.clone(), manifest);
+}
+
+/// Directories searched for local `components/` and `devices/`, lowest
+/// precedence first: each `espforge.module_paths` entry, then the directory of
+/// the configuration itself.
+pub fn local_roots(config: &EspforgeConfiguration) -> Vec<PathBuf> {
+    config
+        .espforge
+        .module_paths
+        .iter()
+        .map(|p| config.base_dir.join(p))
+        .chain(std::iter::once(config.base_dir.clone()))
+        .collect()
+}
+
+/// Manifests from the local roots of `config`, in [`local_roots`] order. Their
+/// `source` is the `.ron` file they were read from.
+pub fn load_local_manifests(config: &EspforgeConfiguration) -> Result<Vec<ComponentManifest>> {
+    let mut manifests = Vec::new();
+    for root in local_roots(config) {
+        manifests.extend(load_root_manifests(&root)?);
+    }
+    Ok(manifests)
+}
+
+/// Manifests under `root`, which is laid out like espforge itself: components
+/// are `components/<name>.ron` with `<name>.rs` next to it; devices are
+/// `devices/<name>/device.ron` with `device.rs` and an optional
+/// `Cargo.toml.tera`.
+fn load_root_manifests(root: &Path) -> Result<Vec<ComponentManifest>> {
+    let mut files = Vec::new();
+    for entry in read_dir_sorted(&root.join("components"))? {
+        if entry.extension().is_some_and(|e| e == "ron") {
+            files.push((ManifestKind::Component, entry));
+        }
+    }
+    for entry in read_dir_sorted(&root.join("devices"))? {
+        let file = entry.join("device.ron");
+        if file.is_file() {
+            files.push((ManifestKind::Device, file));
+        }
+    }
+
+    let mut manifests = Vec::new();
+    for (kind, file) in files {
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read manifest file: {}", file.display()))?;
+        let mut manifest: ComponentManifest = ron::from_str(&content)
+            .with_context(|| format!("Failed to parse manifest file: {}", file.display()))?;
+        manifest.module = module_of(kind, &file);
+        manifest.source = Some(file);
+
+        debug!("Loaded manifest from {}: {}", root.display(), manifest.name);
+        manifests.push(manifest);
+    }
+    Ok(manifests)
+}
+
+/// Entries of `dir`, sorted; none if it does not exist.
+fn read_dir_sorted(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// `led.ron` belongs to `components/led`; `ssd1306/device.ron` to `devices/ssd1306`.
+fn module_of(kind: ManifestKind, path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let name = match path.parent().and_then(|p| p.file_name()) {
+        Some(dir) if stem == "device" => dir.to_str().unwrap_or_default(),
+        _ => stem,
+    };
+    format!("{}/{}", kind.module_dir(), name)
+}
+
+/// The component, device and platform modules a configuration needs: those
+/// of every `using:` manifest plus everything they transitively `uses`.
+pub fn required_modules(
+    config: &EspforgeConfiguration,
+    manifests: &HashMap<String, ComponentManifest>,
+) -> BTreeSet<String> {
+    let by_module: HashMap<&str, &ComponentManifest> = manifests
+        .values()
+        .map(|m| (m.module.as_str(), m))
+        .collect();
+
+    let components = config.components.iter().flatten().map(|(_, c)| &c.using);
+    let devices = config.devices.iter().flatten().map(|(_, d)| &d.using);
+
+    let mut pending: Vec<String> = components
+        .chain(devices)
+        .filter_map(|using| manifests.get(using))
+        .map(|m| m.module.clone())
+        .collect();
+    let mut modules = BTreeSet::new();
+
+    while let Some(module) = pending.pop() {
+        if modules.contains(&module) {
+            continue;
+        }
+        if let Some(manifest) = by_module.get(module.as_str()) {
+            pending.extend(manifest.uses.iter().cloned());
+        }
+        modules.insert(module);
+    }
+
+    debug!("Required modules: {:?}", modules);
+    modules
+}
+
 pub(crate) fn devices_dir() -> &'static Dir<'static> {
     &DEVICES_DIR
 }
@@ -56,5 +259,3 @@ pub(crate) fn platform_dir() -> &'static Dir<'static> {
 pub(crate) fn globals_dir() -> &'static Dir<'static> {
     &GLOBALS_DIR
 }
-
-static PLATFORM_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/platform");
@@ -1,7 +1,31 @@
+use crate::config::{GeneratorBackend, GeneratorOption, PlatformConfig};
+use anyhow::Result;
+
 pub mod cargo;
 pub mod espgenerate;
 pub mod manifest;
 pub mod operations;
+pub mod output;
+pub mod skeleton;
+
+pub use manifest::{
+    ManifestKind, load_config_manifests, load_local_manifests, load_manifests, load_manifests_of,
+    local_roots, required_modules,
+};
+pub use output::{FileStatus, OutputReport, ProjectOutput};
 
-pub use espgenerate::run as generate;
-pub use manifest::load_manifests;
\ No newline at end of file
+/// Stages the project skeleton in `output` with the selected backend.
+pub fn generate(
+    output: &mut ProjectOutput,
+    project_name: &str,
+    platform: PlatformConfig,
+    options: &[GeneratorOption],
+    backend: GeneratorBackend,
+) -> Result<()> {
+    match backend {
+        GeneratorBackend::EspGenerate => {
+            espgenerate::run(output, project_name, &platform.to_string(), options)
+        }
+        GeneratorBackend::Builtin => skeleton::run(output, project_name, platform, options),
+    }
+}
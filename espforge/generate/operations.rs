@@ -1,75 +1,155 @@
 // generate/file_operations.rs - File and directory operations
 
 use super::manifest;
+use super::output::ProjectOutput;
+use crate::manifest::ComponentManifest;
 use anyhow::{Context, Result};
 use include_dir::Dir;
 use log::{debug, info};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub fn copy_components(src_path: &Path) -> Result<()> {
-    let components_path = src_path.join("components");
-    copy_dir_files(
-        manifest::components_dir(), 
-        &components_path, 
-        |path| path.ends_with(".rs")
-    ).context("Failed to copy components")?;
-    info!("Copied component files");
+pub fn copy_components(
+    output: &mut ProjectOutput,
+    modules: &BTreeSet<String>,
+    manifests: &HashMap<String, ComponentManifest>,
+) -> Result<()> {
+    let components_path = Path::new("src/components");
+    let local = local_modules(manifests, modules, "components");
+    let builtin: BTreeSet<String> = modules
+        .iter()
+        .filter(|m| !m.strip_prefix("components/").is_some_and(|n| local.contains_key(n)))
+        .cloned()
+        .collect();
+
+    let mut copied = copy_modules(manifest::components_dir(), "components", output, components_path, &builtin)
+        .context("Failed to copy components")?;
+    copied.extend(copy_local_modules(output, components_path, &local)?);
+    copied.sort();
+
+    generate_mod_file(output, components_path, &copied, true)?;
+    info!("Copied {} component file(s)", copied.len());
     Ok(())
 }
 
-pub fn copy_devices(src_path: &Path) -> Result<()> {
-    let devices_path = src_path.join("devices");
-    fs::create_dir_all(&devices_path)
-        .with_context(|| format!("Failed to create directory: {}", devices_path.display()))?;
-
-    let mut modules = Vec::new();
+pub fn copy_devices(
+    output: &mut ProjectOutput,
+    modules: &BTreeSet<String>,
+    manifests: &HashMap<String, ComponentManifest>,
+) -> Result<()> {
+    let devices_path = Path::new("src/devices");
+    let local = local_modules(manifests, modules, "devices");
+    let mut copied = copy_local_modules(output, devices_path, &local)?;
 
     for subdir in manifest::devices_dir().dirs() {
         let device_name = subdir.path().file_name().unwrap().to_str().unwrap();
-        
+        if !modules.contains(&format!("devices/{}", device_name)) || local.contains_key(device_name) {
+            continue;
+        }
+
         // Find device.rs in the device subdirectory (e.g. devices/ili9341/device.rs)
         if let Some(file) = subdir.files().find(|f| f.path().file_name().and_then(|n| n.to_str()) == Some("device.rs")) {
             // Copy it to src/devices/<device_name>.rs
-            let dest_file = devices_path.join(format!("{}.rs", device_name));
-            fs::write(&dest_file, file.contents())
-                .with_context(|| format!("Failed to write device file: {}", dest_file.display()))?;
-            modules.push(device_name.to_string());
+            output.add(devices_path.join(format!("{}.rs", device_name)), file.contents());
+            copied.push(device_name.to_string());
             debug!("Copied device: {}", device_name);
         }
     }
     
     // Sort modules for deterministic output
-    modules.sort();
+    copied.sort();
 
     // Generate src/devices/mod.rs
-    generate_mod_file(&devices_path, &modules, true)?;
-    info!("Copied device files");
+    generate_mod_file(output, devices_path, &copied, true)?;
+    info!("Copied {} device file(s)", copied.len());
     Ok(())
 }
 
-pub fn copy_platform_files(src_path: &Path) -> Result<()> {
-    let platform_path = src_path.join("platform");
-    copy_dir_files(manifest::platform_dir(), &platform_path, |_| true)
+pub fn copy_platform_files(output: &mut ProjectOutput, modules: &BTreeSet<String>) -> Result<()> {
+    let platform_path = Path::new("src/platform");
+    let copied = copy_modules(manifest::platform_dir(), "platform", output, platform_path, modules)
         .context("Failed to copy platform files")?;
-    info!("Copied platform files");
+
+    generate_mod_file(output, platform_path, &copied, false)?;
+    info!("Copied {} platform file(s)", copied.len());
     Ok(())
 }
 
-pub fn copy_dir_files<F>(dir: &Dir<'_>, dest_path: &Path, filter: F) -> Result<()>
+/// The `<prefix>/<name>` modules in `modules` provided by local or pack manifests, by
+/// name, with the Rust file next to each manifest (`led.rs`, `device.rs`).
+fn local_modules(
+    manifests: &HashMap<String, ComponentManifest>,
+    modules: &BTreeSet<String>,
+    prefix: &str,
+) -> BTreeMap<String, PathBuf> {
+    manifests
+        .values()
+        .filter(|m| modules.contains(&m.module))
+        .filter_map(|m| {
+            let name = m.module.strip_prefix(prefix)?.strip_prefix('/')?;
+            let source = m.source.as_ref()?;
+            Some((name.to_string(), source.with_extension("rs")))
+        })
+        .collect()
+}
+
+/// Copies each local module's Rust file to `<dest_path>/<name>.rs`.
+/// Returns the copied module names.
+fn copy_local_modules(
+    output: &mut ProjectOutput,
+    dest_path: &Path,
+    local: &BTreeMap<String, PathBuf>,
+) -> Result<Vec<String>> {
+    for (name, file) in local {
+        let contents = fs::read(file)
+            .with_context(|| format!("Failed to read local module '{}' from {}", name, file.display()))?;
+        output.add(dest_path.join(format!("{}.rs", name)), contents);
+        debug!("Copied local module: {}", file.display());
+    }
+    Ok(local.keys().cloned().collect())
+}
+
+/// Copies `<name>.rs` from `dir` for every `<prefix>/<name>` in `modules`.
+/// Returns the copied module names, sorted.
+fn copy_modules(
+    dir: &Dir<'_>,
+    prefix: &str,
+    output: &mut ProjectOutput,
+    dest_path: &Path,
+    modules: &BTreeSet<String>,
+) -> Result<Vec<String>> {
+    let mut copied = Vec::new();
+
+    copy_dir_files(dir, output, dest_path, |path| {
+        let Some(name) = path.strip_suffix(".rs") else {
+            return false;
+        };
+        let wanted = modules.contains(&format!("{}/{}", prefix, name));
+        if wanted {
+            copied.push(name.to_string());
+        }
+        wanted
+    })?;
+
+    copied.sort();
+    Ok(copied)
+}
+
+pub fn copy_dir_files<F>(
+    dir: &Dir<'_>,
+    output: &mut ProjectOutput,
+    dest_path: &Path,
+    mut filter: F,
+) -> Result<()>
 where
-    F: Fn(&str) -> bool,
+    F: FnMut(&str) -> bool,
 {
-    fs::create_dir_all(dest_path)
-        .with_context(|| format!("Failed to create directory: {}", dest_path.display()))?;
-
     for file in dir.files() {
         let file_path_str = file.path().to_str().context("Invalid UTF-8 in file path")?;
         
         if filter(file_path_str) {
-            let dest_file = dest_path.join(file.path());
-            fs::write(&dest_file, file.contents())
-                .with_context(|| format!("Failed to write file: {}", dest_file.display()))?;
+            output.add(dest_path.join(file.path()), file.contents());
             debug!("Copied: {}", file_path_str);
         }
     }
@@ -77,11 +157,11 @@ where
     Ok(())
 }
 
-pub fn copy_globals_files(src_path: &Path) -> Result<()> {
-    let globals_path = src_path.join("globals");
+pub fn copy_globals_files(output: &mut ProjectOutput) -> Result<()> {
     copy_dir_files(
         manifest::globals_dir(), 
-        &globals_path, 
+        output,
+        Path::new("src/globals"), 
         |path| path.ends_with(".rs")
     ).context("Failed to copy global files")?;
     info!("Copied global files");
@@ -89,21 +169,22 @@ pub fn copy_globals_files(src_path: &Path) -> Result<()> {
 }
 
 
-pub fn generate_mod_file(dir_path: &Path, modules: &[String], use_pub_use: bool) -> Result<()> {
+pub fn generate_mod_file(
+    output: &mut ProjectOutput,
+    dir_path: &Path,
+    modules: &[String],
+    use_pub_use: bool,
+) -> Result<()> {
     let mut content = String::from("#![allow(unused_imports)]\n");
     
     for module in modules {
         content.push_str(&format!("pub mod {};\n", module));
         if use_pub_use {
             content.push_str(&format!("pub use {}::*;\n", module));
-        } else {
-            content.push_str(&format!("pub use {}::*;\n", module));
         }
     }
     
-    let mod_file = dir_path.join("mod.rs");
-    fs::write(&mod_file, content)
-        .with_context(|| format!("Failed to write mod file: {}", mod_file.display()))?;
+    output.add(dir_path.join("mod.rs"), content);
     
     debug!("Generated mod.rs with {} modules", modules.len());
     Ok(())
@@ -0,0 +1,362 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Records the hash of every file espforge wrote, relative to the project root.
+pub const STATE_FILE: &str = ".espforge-state.json";
+
+const USER_BEGIN: &str = "// espforge:user-begin ";
+const USER_END: &str = "// espforge:user-end ";
+
+/// The files of a generated project, staged in memory and written in one go.
+///
+/// Writing through the staging area is what makes `compile` re-runnable: a file
+/// is only overwritten when its content on disk is still what espforge wrote
+/// last time (ignoring `// espforge:user-begin` regions, which are carried over).
+/// Files espforge never generates, such as user modules, are left alone.
+pub struct ProjectOutput {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, Staged>,
+}
+
+struct Staged {
+    contents: Vec<u8>,
+    merged: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct State {
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Created,
+    Updated,
+    Unchanged,
+    /// Edited outside user regions since the last compile; left as is.
+    Conflict,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileStatus::Created => write!(f, "created"),
+            FileStatus::Updated => write!(f, "updated"),
+            FileStatus::Unchanged => write!(f, "unchanged"),
+            FileStatus::Conflict => write!(f, "conflict"),
+        }
+    }
+}
+
+/// What [`ProjectOutput::write`] did (or [`ProjectOutput::preview`] would do)
+/// to each file.
+#[derive(Debug)]
+pub struct OutputReport {
+    pub root: PathBuf,
+    pub files: Vec<(PathBuf, FileStatus)>,
+    pub dry_run: bool,
+    /// Unified diff of the created and updated files, for a dry run.
+    pub diff: String,
+}
+
+impl OutputReport {
+    pub fn count(&self, status: FileStatus) -> usize {
+        self.files.iter().filter(|(_, s)| *s == status).count()
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.count(FileStatus::Conflict) > 0
+    }
+}
+
+impl fmt::Display for OutputReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.diff)?;
+        for (path, status) in &self.files {
+            writeln!(f, "  {:<9} {}", status.to_string(), path.display())?;
+        }
+        writeln!(
+            f,
+            "{}{}: {} created, {} updated, {} unchanged, {} conflict(s)",
+            if self.dry_run { "(dry run) " } else { "" },
+            self.root.display(),
+            self.count(FileStatus::Created),
+            self.count(FileStatus::Updated),
+            self.count(FileStatus::Unchanged),
+            self.count(FileStatus::Conflict),
+        )
+    }
+}
+
+impl ProjectOutput {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Stages an espforge-owned file, replacing anything staged earlier.
+    pub fn add(&mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        let contents = contents.into();
+        self.files.insert(
+            path.into(),
+            Staged {
+                contents,
+                merged: false,
+            },
+        );
+    }
+
+    /// Stages a file whose new content was derived from the one on disk (e.g.
+    /// `Cargo.toml`), so it already carries any local edits.
+    pub fn add_merged(&mut self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        let contents = contents.into();
+        self.files.insert(
+            path.into(),
+            Staged {
+                contents,
+                merged: true,
+            },
+        );
+    }
+
+    /// Stages every file under `dir` (except `.git`), e.g. a skeleton a
+    /// generator wrote to a scratch directory.
+    pub fn add_dir(&mut self, dir: &Path) -> Result<()> {
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let entries = fs::read_dir(&current)
+                .with_context(|| format!("Failed to read directory: {}", current.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.file_name().is_some_and(|n| n == ".git") {
+                    continue;
+                }
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    let contents = fs::read(&path)
+                        .with_context(|| format!("Failed to read {}", path.display()))?;
+                    let relative = path.strip_prefix(dir).unwrap_or(&path);
+                    self.add(relative, contents);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The staged content of `path`, or else the file on disk.
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        match self.files.get(path) {
+            Some(staged) => String::from_utf8(staged.contents.clone())
+                .with_context(|| format!("{} is not valid UTF-8", path.display())),
+            None => {
+                let full = self.root.join(path);
+                fs::read_to_string(&full)
+                    .with_context(|| format!("Failed to read {}", full.display()))
+            }
+        }
+    }
+
+    /// Writes the staged files. With `force`, conflicting files are overwritten
+    /// (losing edits outside user regions) instead of kept.
+    pub fn write(self, force: bool) -> Result<OutputReport> {
+        self.apply(force, false)
+    }
+
+    /// Reports what [`ProjectOutput::write`] would do, with a unified diff of
+    /// every file it would change, without touching the disk.
+    pub fn preview(self, force: bool) -> Result<OutputReport> {
+        self.apply(force, true)
+    }
+
+    fn apply(self, force: bool, dry_run: bool) -> Result<OutputReport> {
+        let state_path = self.root.join(STATE_FILE);
+        let mut state = match fs::read_to_string(&state_path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", state_path.display()))?,
+            Err(_) => State::default(),
+        };
+
+        let mut report = Vec::new();
+        let mut diff = String::new();
+        for (path, staged) in self.files {
+            let key = path.to_string_lossy().replace('\\', "/");
+            let dest = self.root.join(&path);
+            let existing = fs::read(&dest).ok();
+            let mut contents = staged.contents;
+            let mut lost_region = false;
+
+            if let (Some(old), Ok(new)) = (&existing, std::str::from_utf8(&contents))
+                && let Ok(old) = std::str::from_utf8(old)
+            {
+                let (filled, lost) = fill_user_regions(new, &user_regions(old));
+                contents = filled.into_bytes();
+                lost_region = lost;
+            }
+
+            let status = match &existing {
+                None => FileStatus::Created,
+                Some(old) if *old == contents => FileStatus::Unchanged,
+                Some(_) if staged.merged || force => FileStatus::Updated,
+                Some(old) => {
+                    let recorded = state.files.get(&key);
+                    if !lost_region && recorded == Some(&owned_hash(old)) {
+                        FileStatus::Updated
+                    } else {
+                        FileStatus::Conflict
+                    }
+                }
+            };
+
+            match status {
+                FileStatus::Conflict => {
+                    warn!("{} has local changes; not updating it", dest.display());
+                }
+                FileStatus::Unchanged => {}
+                FileStatus::Created | FileStatus::Updated if dry_run => {
+                    diff.push_str(&unified_diff(&key, existing.as_deref(), &contents));
+                }
+                FileStatus::Created | FileStatus::Updated => {
+                    if let Some(parent) = dest.parent() {
+                        fs::create_dir_all(parent).with_context(|| {
+                            format!("Failed to create directory: {}", parent.display())
+                        })?;
+                    }
+                    fs::write(&dest, &contents)
+                        .with_context(|| format!("Failed to write {}", dest.display()))?;
+                    debug!("{}: {}", status, dest.display());
+                }
+            }
+
+            if status != FileStatus::Conflict && !staged.merged {
+                state.files.insert(key, owned_hash(&contents));
+            }
+            report.push((path, status));
+        }
+
+        if !dry_run {
+            let json = serde_json::to_string_pretty(&state)?;
+            fs::write(&state_path, json + "\n")
+                .with_context(|| format!("Failed to write {}", state_path.display()))?;
+        }
+
+        Ok(OutputReport {
+            root: self.root,
+            files: report,
+            dry_run,
+            diff,
+        })
+    }
+}
+
+/// `git diff`-style unified diff of one file; `old` is `None` for a new file.
+fn unified_diff(path: &str, old: Option<&[u8]>, new: &[u8]) -> String {
+    let old_name = if old.is_some() {
+        format!("a/{}", path)
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_name = format!("b/{}", path);
+
+    let (Ok(old_text), Ok(new_text)) = (
+        std::str::from_utf8(old.unwrap_or_default()),
+        std::str::from_utf8(new),
+    ) else {
+        return format!("Binary files {} and {} differ\n", old_name, new_name);
+    };
+
+    TextDiff::from_lines(old_text, new_text)
+        .unified_diff()
+        .header(&old_name, &new_name)
+        .to_string()
+}
+
+/// Name of the user region a marker line opens or closes.
+fn marker<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    line.trim().strip_prefix(prefix).map(str::trim)
+}
+
+/// The bodies of the `// espforge:user-begin <name>` regions in `text`.
+fn user_regions(text: &str) -> BTreeMap<String, String> {
+    let mut regions = BTreeMap::new();
+    let mut current: Option<(&str, String)> = None;
+
+    for line in text.split_inclusive('\n') {
+        if let Some((name, body)) = current.take() {
+            if marker(line, USER_END) == Some(name) {
+                regions.insert(name.to_string(), body);
+            } else {
+                current = Some((name, body + line));
+            }
+        } else if let Some(name) = marker(line, USER_BEGIN) {
+            current = Some((name, String::new()));
+        }
+    }
+    regions
+}
+
+/// Puts the saved region bodies into the (empty) regions of `text`. Also
+/// reports whether a non-empty region has nowhere to go.
+fn fill_user_regions(text: &str, regions: &BTreeMap<String, String>) -> (String, bool) {
+    let mut out = String::with_capacity(text.len());
+    let mut used = Vec::new();
+    let mut skipping = None;
+
+    for line in text.split_inclusive('\n') {
+        if let Some(name) = skipping {
+            if marker(line, USER_END) != Some(name) {
+                continue;
+            }
+            skipping = None;
+        } else if let Some(name) = marker(line, USER_BEGIN)
+            && let Some(body) = regions.get(name)
+        {
+            out.push_str(line);
+            out.push_str(body);
+            used.push(name);
+            skipping = Some(name);
+            continue;
+        }
+        out.push_str(line);
+    }
+
+    let lost = regions
+        .iter()
+        .any(|(name, body)| !body.trim().is_empty() && !used.contains(&name.as_str()));
+    (out, lost)
+}
+
+/// Hash of `contents` without the bodies of its user regions, so that editing
+/// inside a region does not count as a local change.
+fn owned_hash(contents: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    match std::str::from_utf8(contents) {
+        Ok(text) => {
+            let empty = user_regions(text)
+                .into_keys()
+                .map(|name| (name, String::new()))
+                .collect();
+            hasher.update(fill_user_regions(text, &empty).0);
+        }
+        Err(_) => hasher.update(contents),
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -0,0 +1,140 @@
+use super::output::ProjectOutput;
+use crate::config::{GeneratorOption, PlatformConfig};
+use anyhow::{Context, Result};
+use log::{debug, info};
+
+const CARGO_TOML: &str = r#"[package]
+edition = "2024"
+name = "{{ name }}"
+rust-version = "1.88"
+version = "0.1.0"
+
+[[bin]]
+name = "{{ name }}"
+path = "./src/bin/main.rs"
+
+[dependencies]
+esp-hal = { version = "~1.0", features = ["{{ chip }}", "{{ logging }}"{% if "unstable-hal" in options %}, "unstable"{% endif %}] }
+esp-bootloader-esp-idf = { version = "0.4.0", features = ["{{ chip }}", "{{ logging }}"] }
+{% if "esp-backtrace" in options -%}
+esp-backtrace = { version = "0.18.0", features = ["{{ chip }}", "panic-handler", "{% if defmt %}defmt{% else %}println{% endif %}"] }
+{% endif -%}
+esp-println = { version = "0.16.0", features = ["{{ chip }}", "{% if defmt %}defmt-espflash{% else %}log-04{% endif %}"] }
+{% if defmt -%}
+defmt = "1.0.1"
+{%- else -%}
+log = "0.4.27"
+{%- endif %}
+critical-section = "1.2.0"
+{% if "alloc" in options -%}
+esp-alloc = { version = "0.9.0", features = ["{{ chip }}"] }
+{% endif -%}
+{% if "embassy" in options -%}
+esp-rtos = { version = "0.2.0", features = ["{{ chip }}", "embassy", "{{ logging }}"] }
+embassy-executor = { version = "0.9.0", features = ["{% if defmt %}defmt{% else %}log{% endif %}"] }
+embassy-time = { version = "0.5.0", features = ["{% if defmt %}defmt{% else %}log{% endif %}"] }
+static_cell = "2.1.1"
+{% endif %}
+[profile.dev]
+# Rust debug is too slow.
+# For debug builds always builds with some optimization
+opt-level = "s"
+
+[profile.release]
+codegen-units = 1 # LLVM can perform better optimizations using a single thread
+debug = 2
+debug-assertions = false
+incremental = false
+lto = "fat"
+opt-level = "s"
+overflow-checks = false
+"#;
+
+const CARGO_CONFIG_TOML: &str = r#"[target.{{ target }}]
+runner = "espflash flash --monitor --chip {{ chip }}{% if defmt %} --log-format defmt{% endif %}"
+
+[env]
+{% if defmt %}DEFMT_LOG{% else %}ESP_LOG{% endif %} = "info"
+
+[build]
+rustflags = [
+{%- if xtensa %}
+  "-C", "link-arg=-nostartfiles",
+{%- else %}
+  # Required to obtain backtraces (e.g. when using the "esp-backtrace" crate.)
+  # NOTE: May negatively impact performance of produced code
+  "-C", "force-frame-pointers",
+{%- endif %}
+]
+
+target = "{{ target }}"
+{% if xtensa %}
+[unstable]
+build-std = ["core"]
+{% endif -%}
+"#;
+
+const RUST_TOOLCHAIN_TOML: &str = r#"[toolchain]
+{% if xtensa -%}
+channel = "esp"
+{%- else -%}
+channel = "stable"
+components = ["rust-src"]
+targets = ["{{ target }}"]
+{%- endif %}
+"#;
+
+const BUILD_RS: &str = r#"fn main() {
+{%- if defmt %}
+    println!("cargo:rustc-link-arg=-Tdefmt.x");
+{%- endif %}
+    // esp-hal's linker script, which pulls in the memory layout for the chip
+    println!("cargo:rustc-link-arg=-Tlinkall.x");
+}
+"#;
+
+const GITIGNORE: &str = "/target\n";
+
+/// Stages a project skeleton for `platform` without calling `esp-generate`.
+///
+/// Produces the same layout the esp-generate backend does for the options
+/// [`GeneratorOption::builtin_supported`] covers; `src/` is filled in
+/// afterwards from the espforge templates.
+pub fn run(
+    output: &mut ProjectOutput,
+    project_name: &str,
+    platform: PlatformConfig,
+    options: &[GeneratorOption],
+) -> Result<()> {
+    debug!("Writing built-in skeleton for chip: {}", platform);
+
+    let defmt = options.contains(&GeneratorOption::Defmt);
+    let option_names: Vec<&str> = options.iter().map(|o| o.as_str()).collect();
+
+    let mut context = tera::Context::new();
+    context.insert("name", project_name);
+    context.insert("chip", &platform.to_string());
+    context.insert("target", platform.target());
+    context.insert("xtensa", &platform.is_xtensa());
+    context.insert("options", &option_names);
+    context.insert("defmt", &defmt);
+    context.insert("logging", if defmt { "defmt" } else { "log-04" });
+
+    let files = [
+        ("Cargo.toml", CARGO_TOML),
+        (".cargo/config.toml", CARGO_CONFIG_TOML),
+        ("rust-toolchain.toml", RUST_TOOLCHAIN_TOML),
+        ("build.rs", BUILD_RS),
+        (".gitignore", GITIGNORE),
+    ];
+
+    for (name, template) in files {
+        let rendered = tera::Tera::one_off(template, &context, false)
+            .with_context(|| format!("Failed to render skeleton {}", name))?;
+        output.add(name, rendered);
+        debug!("Rendered: {}", name);
+    }
+
+    info!("Created project skeleton for {}", platform);
+    Ok(())
+}
@@ -2,6 +2,10 @@ use core::fmt::{Display, LowerHex};
 
 pub struct EspforgeLog;
 
+// The `log-defmt` feature is enabled when the project is generated with the
+// `defmt` option instead of `log`.
+
+#[cfg(not(feature = "log-defmt"))]
 impl EspforgeLog {
     pub fn new() -> Self {
         esp_println::logger::init_logger_from_env();
@@ -18,3 +22,18 @@ impl EspforgeLog {
     }
 }
 
+#[cfg(feature = "log-defmt")]
+impl EspforgeLog {
+    pub fn new() -> Self {
+        // defmt needs no runtime setup; the transport is picked at link time
+        Self
+    }
+
+    pub fn info<T: Display>(&self, msg: T) {
+        defmt::info!("{}", defmt::Display2Format(&msg));
+    }
+
+    pub fn print_hex<T: LowerHex>(&self, msg: T) {
+        defmt::info!("{}", defmt::Display2Format(&format_args!("0x{:x}", msg)));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::config::{EspforgeConfiguration, LoadOptions};
+use crate::generate::{ManifestKind, load_config_manifests, load_manifests_of};
+use crate::manifest::{ComponentManifest, ParameterType};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Everything a configuration can reach from `app:` blocks or Ruchy scripts.
+#[derive(Debug, Serialize)]
+pub struct InspectReport {
+    pub project: String,
+    pub platform: String,
+    pub components: Vec<InstanceInfo>,
+    pub devices: Vec<InstanceInfo>,
+    pub globals: Vec<ModuleInfo>,
+}
+
+/// A `components:`/`devices:` entry and the manifest it is built from.
+#[derive(Debug, Serialize)]
+pub struct InstanceInfo {
+    pub instance: String,
+    pub using: String,
+    /// `None` when `using:` does not match any known manifest
+    pub manifest: Option<ModuleInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub parameters: Vec<ParameterInfo>,
+    pub methods: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub param_type: ParameterType,
+    pub required: bool,
+}
+
+impl From<&ComponentManifest> for ModuleInfo {
+    fn from(manifest: &ComponentManifest) -> Self {
+        let mut methods: Vec<String> = manifest.methods.keys().cloned().collect();
+        methods.sort();
+
+        Self {
+            name: manifest.name.clone(),
+            parameters: manifest
+                .parameters
+                .iter()
+                .map(|p| ParameterInfo {
+                    name: p.name.clone(),
+                    param_type: p.param_type,
+                    required: p.required,
+                })
+                .collect(),
+            methods,
+        }
+    }
+}
+
+pub fn inspect(path: &Path) -> Result<InspectReport> {
+    let config = EspforgeConfiguration::load(path, &LoadOptions::default())?;
+
+    let manifests = load_config_manifests(&config)?;
+
+    let components = config
+        .components
+        .as_ref()
+        .map(|c| describe_instances(c.iter().map(|(k, v)| (k, &v.using)), &manifests))
+        .unwrap_or_default();
+
+    let devices = config
+        .devices
+        .as_ref()
+        .map(|d| describe_instances(d.iter().map(|(k, v)| (k, &v.using)), &manifests))
+        .unwrap_or_default();
+
+    let mut globals: Vec<ModuleInfo> = load_manifests_of(ManifestKind::Global)?
+        .values()
+        .map(ModuleInfo::from)
+        .collect();
+    globals.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(InspectReport {
+        project: config.get_name().to_string(),
+        platform: config.get_platform(),
+        components,
+        devices,
+        globals,
+    })
+}
+
+fn describe_instances<'a>(
+    instances: impl Iterator<Item = (&'a String, &'a String)>,
+    manifests: &HashMap<String, ComponentManifest>,
+) -> Vec<InstanceInfo> {
+    let mut infos: Vec<InstanceInfo> = instances
+        .map(|(name, using)| InstanceInfo {
+            instance: name.clone(),
+            using: using.clone(),
+            manifest: manifests.get(using).map(ModuleInfo::from),
+        })
+        .collect();
+    infos.sort_by(|a, b| a.instance.cmp(&b.instance));
+    infos
+}
+
+impl fmt::Display for InspectReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Project: {} ({})", self.project, self.platform)?;
+
+        for (title, instances) in [("Components", &self.components), ("Devices", &self.devices)] {
+            writeln!(f, "\n{}:", title)?;
+            if instances.is_empty() {
+                writeln!(f, "  (none)")?;
+            }
+            for instance in instances {
+                match &instance.manifest {
+                    Some(module) => {
+                        writeln!(f, "  {} (using {})", instance.instance, module.name)?;
+                        write_module_details(f, module)?;
+                    }
+                    None => writeln!(
+                        f,
+                        "  {} (using {}, unknown manifest)",
+                        instance.instance, instance.using
+                    )?,
+                }
+            }
+        }
+
+        writeln!(f, "\nGlobals:")?;
+        for module in &self.globals {
+            writeln!(f, "  {}", module.name)?;
+            write_module_details(f, module)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_module_details(f: &mut fmt::Formatter, module: &ModuleInfo) -> fmt::Result {
+    if !module.parameters.is_empty() {
+        writeln!(f, "    parameters:")?;
+        for param in &module.parameters {
+            let required = if param.required { "required" } else { "optional" };
+            writeln!(f, "      {}: {:?} ({})", param.name, param.param_type, required)?;
+        }
+    }
+    writeln!(f, "    methods: {}", module.methods.join(", "))
+}
@@ -1,10 +1,17 @@
+pub mod board;
 pub mod compile;
 pub mod config;
 pub mod export;
 pub mod generate;
+pub mod inspect;
 pub mod manifest;
+pub mod metadata;
+#[cfg(feature = "cli")]
+pub mod menuconfig;
 pub mod nibblers;
+pub mod pack;
 pub mod resolver;
+pub mod schema;
 pub mod template_utils;
 
 pub mod templates {
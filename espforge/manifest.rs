@@ -1,23 +1,35 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ComponentManifest {
     pub name: String,
     pub requires: Vec<String>,
+    /// Other modules of the generated crate this one's Rust code needs,
+    /// e.g. `"platform/i2c"` or `"components/spi"`
+    #[serde(default)]
+    pub uses: Vec<String>,
+    /// Module holding this manifest's Rust code, e.g. `"devices/ssd1306"`.
+    /// Set when the manifest is loaded.
+    #[serde(skip)]
+    pub module: String,
+    /// The `.ron` file of a local or pack manifest; `None` for the embedded ones.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
     pub parameters: Vec<ParameterDef>,
     pub setup_template: String,
     pub methods: HashMap<String, MethodDef>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ParameterDef {
     pub name: String,
     pub param_type: ParameterType,
     pub required: bool,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum ParameterType {
     GpioRef,
     I2cRef,
@@ -30,7 +42,37 @@ pub enum ParameterType {
     Boolean,
 }
 
-#[derive(Debug, Deserialize)]
+impl ParameterType {
+    /// Whether several instances may reference the same `$resource` through
+    /// a parameter of this type. Hardware handles are moved into the instance
+    /// that consumes them; bus components are borrowed, so devices can share them.
+    pub fn is_shareable(&self) -> bool {
+        match self {
+            ParameterType::GpioRef
+            | ParameterType::I2cRef
+            | ParameterType::SpiRef
+            | ParameterType::UartRef => false,
+            ParameterType::I2cComponentRef
+            | ParameterType::SpiComponentRef
+            | ParameterType::String
+            | ParameterType::Integer
+            | ParameterType::Boolean => true,
+        }
+    }
+
+    /// For bus component references, the hardware reference a component must
+    /// take to be a valid target: an `I2cComponentRef` must name a component
+    /// that owns an I2C bus.
+    pub fn bus_ref(&self) -> Option<ParameterType> {
+        match self {
+            ParameterType::I2cComponentRef => Some(ParameterType::I2cRef),
+            ParameterType::SpiComponentRef => Some(ParameterType::SpiRef),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct MethodDef {
     pub template: String,
 }
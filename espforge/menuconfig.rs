@@ -0,0 +1,989 @@
+use crate::config::{
+    ComponentConfig, DRIVE_STRENGTHS, DeviceConfig, Esp32Config, EspforgeConfig, EspforgeConfiguration,
+    GeneratorBackend, GeneratorConfig, GeneratorOption, GpioPinConfig, I2cConfig, LoadOptions, PinDirection, PinLevel, PlatformConfig,
+    SpiConfig, UartConfig,
+};
+use crate::config::board_overlay::restore_board;
+use crate::config::profiles::PROFILES_KEY;
+use crate::config::{include, substitutions};
+use crate::generate::{ManifestKind, load_config_manifests};
+use crate::manifest::{ComponentManifest, ParameterDef, ParameterType};
+use crate::nibblers::{NibblerDispatcher, NibblerStatus};
+use anyhow::{Context, Result};
+use dialoguer::{Confirm, Input, MultiSelect, Select, theme::ColorfulTheme};
+use serde_yaml_ng::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const BACK: &str = "< Back";
+const ADD: &str = "+ Add new";
+
+/// Interactive, menuconfig-style editor for an espforge YAML configuration.
+///
+/// Creates the file if it does not exist yet.
+pub fn run(path: &Path) -> Result<()> {
+    let theme = ColorfulTheme::default();
+
+    let (mut config, original, header) = if path.exists() {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config at {}", path.display()))?;
+        let document: Value =
+            serde_yaml_ng::from_str(&content).context("Failed to parse YAML configuration")?;
+        // Saving would replace every reference with its value, inline
+        // included files and drop profiles
+        if substitutions::is_used(&document)
+            || include::needs_loading(&document)
+            || document.get(PROFILES_KEY).is_some()
+        {
+            anyhow::bail!(
+                "{} uses substitutions, !include, packages or profiles, which menuconfig cannot edit; edit the file directly",
+                path.display()
+            );
+        }
+        let config = EspforgeConfiguration::parse(&content, &LoadOptions::default())?;
+        (config, Some(document), leading_comments(&content))
+    } else {
+        (new_configuration(&theme, path)?, None, String::new())
+    };
+    config.set_base_dir_from(path);
+
+    let manifests = load_config_manifests(&config)?;
+    let (components, devices) = manifests
+        .into_iter()
+        .partition(|(_, m)| ManifestKind::of(m) == Some(ManifestKind::Component));
+
+    let mut editor = MenuConfig {
+        theme,
+        config,
+        original,
+        header,
+        components,
+        devices,
+    };
+    editor.main_menu(path)
+}
+
+fn new_configuration(theme: &ColorfulTheme, path: &Path) -> Result<EspforgeConfiguration> {
+    let default_name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("espforge_project")
+        .to_string();
+
+    let name: String = Input::with_theme(theme)
+        .with_prompt("Project name")
+        .default(default_name)
+        .interact_text()?;
+
+    let platform = select_platform(theme, PlatformConfig::ESP32C3)?;
+
+    Ok(EspforgeConfiguration {
+        substitutions: Default::default(),
+        espforge: EspforgeConfig {
+            name,
+            platform,
+            board: None,
+            wokwi_board: None,
+            wokwi: None,
+            enable_async: false,
+            generator: None,
+            output: None,
+            module_paths: Vec::new(),
+        },
+        example: None,
+        esp32: None,
+        components: None,
+        devices: None,
+        app: None,
+        base_dir: Default::default(),
+        profile: None,
+    })
+}
+
+fn select_platform(theme: &ColorfulTheme, current: PlatformConfig) -> Result<PlatformConfig> {
+    let default = PlatformConfig::ALL
+        .iter()
+        .position(|p| *p == current)
+        .unwrap_or(0);
+    let selection = Select::with_theme(theme)
+        .with_prompt("Target chip")
+        .items(PlatformConfig::ALL.iter())
+        .default(default)
+        .interact()?;
+    Ok(PlatformConfig::ALL[selection])
+}
+
+struct MenuConfig {
+    theme: ColorfulTheme,
+    config: EspforgeConfiguration,
+    /// The file as read, to write board pin labels back as they were and
+    /// keep its key order
+    original: Option<Value>,
+    /// Comment lines at the top of the file (e.g. the `$schema` modeline),
+    /// which are written back
+    header: String,
+    components: HashMap<String, ComponentManifest>,
+    devices: HashMap<String, ComponentManifest>,
+}
+
+#[derive(Clone, Copy)]
+enum HardwareSection {
+    Gpio,
+    Spi,
+    I2c,
+    Uart,
+}
+
+impl HardwareSection {
+    const ALL: [HardwareSection; 4] = [
+        HardwareSection::Gpio,
+        HardwareSection::Spi,
+        HardwareSection::I2c,
+        HardwareSection::Uart,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            HardwareSection::Gpio => "gpio",
+            HardwareSection::Spi => "spi",
+            HardwareSection::I2c => "i2c",
+            HardwareSection::Uart => "uart",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum InstanceSection {
+    Components,
+    Devices,
+}
+
+impl MenuConfig {
+    fn main_menu(&mut self, path: &Path) -> Result<()> {
+        loop {
+            let items = [
+                "Project settings",
+                "ESP32 hardware (gpio/spi/i2c/uart)",
+                "Components",
+                "Devices",
+                "Save",
+                "Save and exit",
+                "Exit without saving",
+            ];
+            let selection = Select::with_theme(&self.theme)
+                .with_prompt(format!("espforge configuration: {}", path.display()))
+                .items(items)
+                .default(0)
+                .interact()?;
+
+            match selection {
+                0 => self.edit_project()?,
+                1 => self.hardware_menu()?,
+                2 => self.instance_menu(InstanceSection::Components)?,
+                3 => self.instance_menu(InstanceSection::Devices)?,
+                4 => {
+                    self.save(path)?;
+                }
+                5 => {
+                    if self.save(path)? {
+                        return Ok(());
+                    }
+                }
+                _ => {
+                    let discard = Confirm::with_theme(&self.theme)
+                        .with_prompt("Discard unsaved changes?")
+                        .default(false)
+                        .interact()?;
+                    if discard {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn edit_project(&mut self) -> Result<()> {
+        let espforge = &mut self.config.espforge;
+        espforge.name = Input::with_theme(&self.theme)
+            .with_prompt("Project name")
+            .default(espforge.name.clone())
+            .interact_text()?;
+        espforge.platform = select_platform(&self.theme, espforge.platform)?;
+        let output: String = Input::with_theme(&self.theme)
+            .with_prompt(format!("Output directory (empty for ./{})", espforge.name))
+            .with_initial_text(
+                espforge
+                    .output
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+            )
+            .allow_empty(true)
+            .interact_text()?;
+        espforge.output = (!output.trim().is_empty()).then(|| output.trim().into());
+        espforge.enable_async = Confirm::with_theme(&self.theme)
+            .with_prompt("Enable async (embassy)?")
+            .default(espforge.enable_async)
+            .interact()?;
+
+        let current = espforge
+            .generator
+            .as_ref()
+            .map(|g| g.backend)
+            .unwrap_or_default();
+        let labels: Vec<String> = GeneratorBackend::ALL.iter().map(|b| b.to_string()).collect();
+        let selection = Select::with_theme(&self.theme)
+            .with_prompt("Project generator")
+            .items(&labels)
+            .default(
+                GeneratorBackend::ALL
+                    .iter()
+                    .position(|b| *b == current)
+                    .unwrap_or(0),
+            )
+            .interact()?;
+        let backend = GeneratorBackend::ALL[selection];
+
+        // `embassy` follows `enable_async`, so it is not offered here
+        let choices: Vec<GeneratorOption> = GeneratorOption::ALL
+            .into_iter()
+            .filter(|o| *o != GeneratorOption::Embassy)
+            .collect();
+        let current_options = espforge
+            .generator
+            .as_ref()
+            .and_then(|g| g.options.clone())
+            .unwrap_or_else(|| GeneratorConfig::DEFAULT_OPTIONS.to_vec());
+        let checked: Vec<bool> = choices
+            .iter()
+            .map(|o| current_options.contains(o))
+            .collect();
+        let labels: Vec<&str> = choices.iter().map(|o| o.as_str()).collect();
+        let selected = MultiSelect::with_theme(&self.theme)
+            .with_prompt("Generator options (space to toggle)")
+            .items(&labels)
+            .defaults(&checked)
+            .interact()?;
+        let options: Vec<GeneratorOption> = selected.into_iter().map(|i| choices[i]).collect();
+        let options = (options != GeneratorConfig::DEFAULT_OPTIONS).then_some(options);
+
+        if espforge.generator.is_some()
+            || backend != GeneratorBackend::default()
+            || options.is_some()
+        {
+            espforge.generator = Some(GeneratorConfig { backend, options });
+        }
+        Ok(())
+    }
+
+    // --- ESP32 hardware ---
+
+    fn hardware_menu(&mut self) -> Result<()> {
+        loop {
+            let mut items: Vec<&str> = HardwareSection::ALL.iter().map(|s| s.label()).collect();
+            items.push(BACK);
+
+            let selection = Select::with_theme(&self.theme)
+                .with_prompt("ESP32 hardware")
+                .items(&items)
+                .default(0)
+                .interact()?;
+
+            match HardwareSection::ALL.get(selection) {
+                Some(section) => self.hardware_section_menu(*section)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    fn hardware_section_menu(&mut self, section: HardwareSection) -> Result<()> {
+        loop {
+            let esp32 = self.config.esp32.get_or_insert_with(Esp32Config::default);
+            let mut entries = hardware_entries(esp32, section);
+            entries.sort();
+            let (names, mut items): (Vec<String>, Vec<String>) = entries.into_iter().unzip();
+
+            items.push(ADD.to_string());
+            items.push(BACK.to_string());
+
+            let selection = Select::with_theme(&self.theme)
+                .with_prompt(format!("esp32.{}", section.label()))
+                .items(&items)
+                .default(0)
+                .interact()?;
+
+            if selection == names.len() {
+                let name: String = Input::with_theme(&self.theme)
+                    .with_prompt(format!("Name of the new {} resource", section.label()))
+                    .interact_text()?;
+                self.edit_hardware(section, &name)?;
+            } else if let Some(name) = names.get(selection) {
+                self.edit_or_remove(
+                    name,
+                    |menu, name| menu.edit_hardware(section, name),
+                    |menu, name| {
+                        let esp32 = menu.config.esp32.get_or_insert_with(Esp32Config::default);
+                        match section {
+                            HardwareSection::Gpio => esp32.gpio.remove(name).is_some(),
+                            HardwareSection::Spi => esp32.spi.remove(name).is_some(),
+                            HardwareSection::I2c => esp32.i2c.remove(name).is_some(),
+                            HardwareSection::Uart => esp32.uart.remove(name).is_some(),
+                        };
+                    },
+                )?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn edit_hardware(&mut self, section: HardwareSection, name: &str) -> Result<()> {
+        let theme = &self.theme;
+        let esp32 = self.config.esp32.get_or_insert_with(Esp32Config::default);
+
+        match section {
+            HardwareSection::Gpio => {
+                let current = esp32.gpio.get(name).copied();
+                let directions = [
+                    PinDirection::Input,
+                    PinDirection::Output,
+                    PinDirection::InputOutput,
+                ];
+                let direction = Select::with_theme(theme)
+                    .with_prompt("direction")
+                    .items(["input", "output", "input_output"])
+                    .default(current.map_or(1, |c| {
+                        directions
+                            .iter()
+                            .position(|d| *d == c.direction)
+                            .unwrap_or(1)
+                    }))
+                    .interact()?;
+                let direction = directions[direction];
+                let pin = prompt_number(theme, "pin", current.map(|c| c.pin))?;
+                let pullup = prompt_bool(theme, "pullup", current.is_some_and(|c| c.pullup))?;
+                let pulldown =
+                    prompt_bool(theme, "pulldown", current.is_some_and(|c| c.pulldown))?;
+                let (open_drain, drive_strength, initial) = if direction.is_output() {
+                    let open_drain =
+                        prompt_bool(theme, "open_drain", current.is_some_and(|c| c.open_drain))?;
+                    let drive_strength =
+                        prompt_drive_strength(theme, current.and_then(|c| c.drive_strength))?;
+                    let high = prompt_bool(
+                        theme,
+                        "initial level high",
+                        current.is_some_and(|c| c.initial == PinLevel::High),
+                    )?;
+                    let initial = if high { PinLevel::High } else { PinLevel::Low };
+                    (open_drain, drive_strength, initial)
+                } else {
+                    (false, None, PinLevel::Low)
+                };
+                let config = GpioPinConfig {
+                    pin,
+                    direction,
+                    pullup,
+                    pulldown,
+                    open_drain,
+                    drive_strength,
+                    initial,
+                    inverted: prompt_bool(
+                        theme,
+                        "inverted (active-low)",
+                        current.is_some_and(|c| c.inverted),
+                    )?,
+                };
+                esp32.gpio.insert(name.to_string(), config);
+            }
+            HardwareSection::Spi => {
+                let current = esp32.spi.get(name);
+                let config = SpiConfig {
+                    spi: prompt_number(
+                        theme,
+                        "spi (bus number)",
+                        Some(current.map_or(2, |c| c.spi)),
+                    )?,
+                    sck: prompt_number(theme, "sck", current.map(|c| c.sck))?,
+                    mosi: prompt_number(theme, "mosi", current.map(|c| c.mosi))?,
+                    miso: prompt_optional_number(theme, "miso", current.and_then(|c| c.miso))?,
+                    cs: prompt_optional_number(theme, "cs", current.and_then(|c| c.cs))?,
+                    frequency: prompt_number(
+                        theme,
+                        "frequency (kHz)",
+                        Some(current.map_or(1000, |c| c.frequency)),
+                    )?,
+                    mode: prompt_number(theme, "mode", Some(current.map_or(0, |c| c.mode)))?,
+                };
+                esp32.spi.insert(name.to_string(), config);
+            }
+            HardwareSection::I2c => {
+                let current = esp32.i2c.get(name);
+                let config = I2cConfig {
+                    i2c: prompt_number(
+                        theme,
+                        "i2c (bus number)",
+                        Some(current.map_or(0, |c| c.i2c)),
+                    )?,
+                    sda: prompt_number(theme, "sda", current.map(|c| c.sda))?,
+                    scl: prompt_number(theme, "scl", current.map(|c| c.scl))?,
+                    frequency: prompt_number(
+                        theme,
+                        "frequency (kHz)",
+                        Some(current.map_or(100, |c| c.frequency)),
+                    )?,
+                };
+                esp32.i2c.insert(name.to_string(), config);
+            }
+            HardwareSection::Uart => {
+                let current = esp32.uart.get(name);
+                let config = UartConfig {
+                    uart: prompt_number(
+                        theme,
+                        "uart (port number)",
+                        Some(current.map_or(0, |c| c.uart)),
+                    )?,
+                    tx: prompt_number(theme, "tx", current.map(|c| c.tx))?,
+                    rx: prompt_number(theme, "rx", current.map(|c| c.rx))?,
+                    baud: prompt_number(theme, "baud", Some(current.map_or(9600, |c| c.baud)))?,
+                };
+                esp32.uart.insert(name.to_string(), config);
+            }
+        }
+        Ok(())
+    }
+
+    // --- Components and devices ---
+
+    fn instance_menu(&mut self, section: InstanceSection) -> Result<()> {
+        loop {
+            let mut entries: Vec<(String, String)> = match section {
+                InstanceSection::Components => self
+                    .config
+                    .components
+                    .iter()
+                    .flatten()
+                    .map(|(k, v)| (k.clone(), v.using.clone()))
+                    .collect(),
+                InstanceSection::Devices => self
+                    .config
+                    .devices
+                    .iter()
+                    .flatten()
+                    .map(|(k, v)| (k.clone(), v.using.clone()))
+                    .collect(),
+            };
+            entries.sort();
+
+            let mut items: Vec<String> = entries
+                .iter()
+                .map(|(name, using)| format!("{} (using {})", name, using))
+                .collect();
+            items.push(ADD.to_string());
+            items.push(BACK.to_string());
+
+            let title = match section {
+                InstanceSection::Components => "components",
+                InstanceSection::Devices => "devices",
+            };
+            let selection = Select::with_theme(&self.theme)
+                .with_prompt(title)
+                .items(&items)
+                .default(0)
+                .interact()?;
+
+            if selection == entries.len() {
+                self.add_instance(section)?;
+            } else if selection < entries.len() {
+                let (name, _) = entries[selection].clone();
+                self.edit_or_remove(
+                    &name,
+                    |menu, name| menu.edit_instance(section, name, None),
+                    |menu, name| match section {
+                        InstanceSection::Components => {
+                            if let Some(c) = menu.config.components.as_mut() {
+                                c.remove(name);
+                            }
+                        }
+                        InstanceSection::Devices => {
+                            if let Some(d) = menu.config.devices.as_mut() {
+                                d.remove(name);
+                            }
+                        }
+                    },
+                )?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn add_instance(&mut self, section: InstanceSection) -> Result<()> {
+        let manifests = match section {
+            InstanceSection::Components => &self.components,
+            InstanceSection::Devices => &self.devices,
+        };
+        let mut names: Vec<&String> = manifests.keys().collect();
+        names.sort();
+
+        let selection = Select::with_theme(&self.theme)
+            .with_prompt("using")
+            .items(&names)
+            .default(0)
+            .interact()?;
+        let using = names[selection].clone();
+
+        let name: String = Input::with_theme(&self.theme)
+            .with_prompt("Instance name")
+            .interact_text()?;
+
+        self.edit_instance(section, &name, Some(using))
+    }
+
+    fn edit_instance(
+        &mut self,
+        section: InstanceSection,
+        name: &str,
+        using: Option<String>,
+    ) -> Result<()> {
+        let (using, current) = match section {
+            InstanceSection::Components => {
+                let existing = self.config.components.as_ref().and_then(|c| c.get(name));
+                (
+                    using.or_else(|| existing.map(|c| c.using.clone())),
+                    existing.map(|c| c.with.clone()).unwrap_or_default(),
+                )
+            }
+            InstanceSection::Devices => {
+                let existing = self.config.devices.as_ref().and_then(|d| d.get(name));
+                (
+                    using.or_else(|| existing.map(|d| d.using.clone())),
+                    existing.map(|d| d.with.clone()).unwrap_or_default(),
+                )
+            }
+        };
+        let using = using.context("Instance has no 'using' manifest")?;
+
+        let manifests = match section {
+            InstanceSection::Components => &self.components,
+            InstanceSection::Devices => &self.devices,
+        };
+        let manifest = manifests
+            .get(&using)
+            .with_context(|| format!("Manifest '{}' not found", using))?;
+
+        let mut with = HashMap::new();
+        for param in &manifest.parameters {
+            if let Some(value) = self.prompt_parameter(name, param, current.get(&param.name))? {
+                with.insert(param.name.clone(), value);
+            }
+        }
+
+        match section {
+            InstanceSection::Components => {
+                self.config
+                    .components
+                    .get_or_insert_with(HashMap::new)
+                    .insert(name.to_string(), ComponentConfig { using, with });
+            }
+            InstanceSection::Devices => {
+                self.config
+                    .devices
+                    .get_or_insert_with(HashMap::new)
+                    .insert(name.to_string(), DeviceConfig { using, with });
+            }
+        }
+        Ok(())
+    }
+
+    fn prompt_parameter(
+        &self,
+        instance: &str,
+        param: &ParameterDef,
+        current: Option<&Value>,
+    ) -> Result<Option<Value>> {
+        let label = if param.required {
+            format!("{} ({:?})", param.name, param.param_type)
+        } else {
+            format!("{} ({:?}, optional)", param.name, param.param_type)
+        };
+
+        match param.param_type {
+            ParameterType::Integer => {
+                let default = current.and_then(|v| v.as_i64()).map(|v| v.to_string());
+                let number = prompt_parsed::<i64>(
+                    &self.theme,
+                    &label,
+                    default,
+                    !param.required,
+                    "an integer",
+                    |_| Ok(()),
+                )?;
+                Ok(number.map(Value::from))
+            }
+            ParameterType::String => {
+                let default = current.and_then(|v| v.as_str()).map(str::to_string);
+                let input = prompt_text(&self.theme, &label, default, !param.required)?;
+                Ok((!input.is_empty()).then_some(Value::from(input)))
+            }
+            ParameterType::Boolean => {
+                let default = current.and_then(|v| v.as_bool()).unwrap_or(false);
+                Ok(Some(Value::from(prompt_bool(
+                    &self.theme,
+                    &label,
+                    default,
+                )?)))
+            }
+            _ => {
+                let candidates = self.compatible_references(instance, param.param_type);
+                if candidates.is_empty() {
+                    println!(
+                        "No compatible resources for '{}' ({:?}). Define one first and edit '{}' again.",
+                        param.name, param.param_type, instance
+                    );
+                    return Ok(None);
+                }
+
+                let mut items: Vec<String> = candidates.iter().map(|c| format!("${}", c)).collect();
+                if !param.required {
+                    items.push("(none)".to_string());
+                }
+                let default = current
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| items.iter().position(|i| i == s))
+                    .unwrap_or(0);
+
+                let selection = Select::with_theme(&self.theme)
+                    .with_prompt(label)
+                    .items(&items)
+                    .default(default)
+                    .interact()?;
+                Ok(candidates
+                    .get(selection)
+                    .map(|c| Value::from(format!("${}", c))))
+            }
+        }
+    }
+
+    /// Lists the names a `$` reference of the given type may point at.
+    fn compatible_references(&self, instance: &str, param_type: ParameterType) -> Vec<String> {
+        let esp32 = self.config.esp32.as_ref();
+        let mut names: Vec<String> = match param_type {
+            ParameterType::GpioRef => esp32.map(|e| e.gpio.keys().cloned().collect()),
+            ParameterType::SpiRef => esp32.map(|e| e.spi.keys().cloned().collect()),
+            ParameterType::I2cRef => esp32.map(|e| e.i2c.keys().cloned().collect()),
+            ParameterType::UartRef => esp32.map(|e| e.uart.keys().cloned().collect()),
+            ParameterType::I2cComponentRef => {
+                Some(self.components_wrapping(ParameterType::I2cRef, instance))
+            }
+            ParameterType::SpiComponentRef => {
+                Some(self.components_wrapping(ParameterType::SpiRef, instance))
+            }
+            ParameterType::String | ParameterType::Integer | ParameterType::Boolean => None,
+        }
+        .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Components whose manifest takes a hardware reference of `hardware` type.
+    fn components_wrapping(&self, hardware: ParameterType, instance: &str) -> Vec<String> {
+        self.config
+            .components
+            .iter()
+            .flatten()
+            .filter(|(name, _)| name.as_str() != instance)
+            .filter(|(_, component)| {
+                self.components
+                    .get(&component.using)
+                    .is_some_and(|m| m.parameters.iter().any(|p| p.param_type == hardware))
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    // --- Helpers ---
+
+    fn edit_or_remove<E, R>(&mut self, name: &str, edit: E, remove: R) -> Result<()>
+    where
+        E: FnOnce(&mut Self, &str) -> Result<()>,
+        R: FnOnce(&mut Self, &str),
+    {
+        let selection = Select::with_theme(&self.theme)
+            .with_prompt(name)
+            .items(["Edit", "Remove", BACK])
+            .default(0)
+            .interact()?;
+        match selection {
+            0 => edit(self, name),
+            1 => {
+                remove(self, name);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates with the nibblers and writes the file. Returns false if the user backed out.
+    fn save(&self, path: &Path) -> Result<bool> {
+        let results = NibblerDispatcher::new().process_config(&self.config);
+        let errors: Vec<_> = results
+            .iter()
+            .filter(|r| r.status == NibblerStatus::Error)
+            .collect();
+
+        if !errors.is_empty() {
+            for res in &errors {
+                eprintln!("== {} ==", res.nibbler_name);
+                for finding in &res.findings {
+                    eprintln!("  ❌ {}", finding);
+                }
+            }
+            let save_anyway = Confirm::with_theme(&self.theme)
+                .with_prompt("The configuration has errors. Save anyway?")
+                .default(false)
+                .interact()?;
+            if !save_anyway {
+                return Ok(false);
+            }
+        }
+
+        let mut document = serde_yaml_ng::to_value(&self.config)?;
+        sort_entries(&mut document);
+        if let Some(original) = &self.original {
+            restore_board(&mut document, original)?;
+            keep_order(&mut document, original);
+        }
+        let yaml = self.header.clone() + &serde_yaml_ng::to_string(&document)?;
+        fs::write(path, yaml).with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Saved {}", path.display());
+        Ok(true)
+    }
+}
+
+/// The comment and blank lines at the top of `content`.
+fn leading_comments(content: &str) -> String {
+    content
+        .split_inclusive('\n')
+        .take_while(|line| line.trim().is_empty() || line.trim_start().starts_with('#'))
+        .collect()
+}
+
+/// Sorts the named entries the configuration keeps in a `HashMap` (pins,
+/// buses, components, devices, their `with:` parameters and app variables),
+/// which would otherwise be written in a different order on every save.
+fn sort_entries(document: &mut Value) {
+    if let Some(esp32) = document.get_mut("esp32") {
+        for section in ["gpio", "spi", "i2c", "uart"] {
+            sort_mapping(esp32.get_mut(section));
+        }
+    }
+    for section in ["components", "devices"] {
+        if let Some(Value::Mapping(entries)) = document.get_mut(section) {
+            for entry in entries.values_mut() {
+                sort_mapping(entry.get_mut("with"));
+            }
+        }
+        sort_mapping(document.get_mut(section));
+    }
+    sort_mapping(document.get_mut("app").and_then(|app| app.get_mut("variables")));
+}
+
+fn sort_mapping(value: Option<&mut Value>) {
+    if let Some(Value::Mapping(map)) = value {
+        let mut entries: Vec<(Value, Value)> = std::mem::take(map).into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.as_str().cmp(&b.as_str()));
+        *map = entries.into_iter().collect();
+    }
+}
+
+/// Puts the keys of `document` in the order they have in `original`, the file
+/// as it was read; keys added while editing follow them.
+fn keep_order(document: &mut Value, original: &Value) {
+    match (document, original) {
+        (Value::Mapping(map), Value::Mapping(original)) => {
+            let mut rest = std::mem::take(map);
+            for (key, written) in original {
+                if let Some(mut value) = rest.shift_remove(key) {
+                    keep_order(&mut value, written);
+                    map.insert(key.clone(), value);
+                }
+            }
+            map.extend(rest);
+        }
+        (Value::Sequence(items), Value::Sequence(original)) => {
+            for (item, written) in items.iter_mut().zip(original) {
+                keep_order(item, written);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The entries of an `esp32:` section as (name, menu label) pairs.
+fn hardware_entries(esp32: &Esp32Config, section: HardwareSection) -> Vec<(String, String)> {
+    let entry = |name: &String, details: String| (name.clone(), format!("{} ({})", name, details));
+    match section {
+        HardwareSection::Gpio => esp32
+            .gpio
+            .iter()
+            .map(|(k, v)| entry(k, format!("pin {}, {:?}", v.pin, v.direction)))
+            .collect(),
+        HardwareSection::Spi => esp32
+            .spi
+            .iter()
+            .map(|(k, v)| entry(k, format!("spi{}, sck {}, mosi {}", v.spi, v.sck, v.mosi)))
+            .collect(),
+        HardwareSection::I2c => esp32
+            .i2c
+            .iter()
+            .map(|(k, v)| entry(k, format!("i2c{}, sda {}, scl {}", v.i2c, v.sda, v.scl)))
+            .collect(),
+        HardwareSection::Uart => esp32
+            .uart
+            .iter()
+            .map(|(k, v)| entry(k, format!("uart{}, tx {}, rx {}", v.uart, v.tx, v.rx)))
+            .collect(),
+    }
+}
+
+fn prompt_text(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    default: Option<String>,
+    allow_empty: bool,
+) -> Result<String> {
+    let mut input = Input::<String>::with_theme(theme)
+        .with_prompt(prompt)
+        .allow_empty(allow_empty);
+    if let Some(default) = default {
+        input = input.default(default);
+    }
+    Ok(input.interact_text()?)
+}
+
+fn prompt_number<T>(theme: &ColorfulTheme, prompt: &str, default: Option<T>) -> Result<T>
+where
+    T: Clone + ToString + std::str::FromStr,
+    T::Err: ToString + std::fmt::Debug,
+{
+    let mut input = Input::<T>::with_theme(theme).with_prompt(prompt);
+    if let Some(default) = default {
+        input = input.default(default);
+    }
+    Ok(input.interact_text()?)
+}
+
+fn prompt_optional_number(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    default: Option<u8>,
+) -> Result<Option<u8>> {
+    let label = format!("{} (empty for none)", prompt);
+    prompt_parsed(
+        theme,
+        &label,
+        default.map(|d| d.to_string()),
+        true,
+        "a pin number",
+        |_| Ok(()),
+    )
+}
+
+fn prompt_drive_strength(theme: &ColorfulTheme, default: Option<u8>) -> Result<Option<u8>> {
+    prompt_parsed(
+        theme,
+        "drive_strength (mA: 5, 10, 20 or 40, empty for esp-hal's default)",
+        default.map(|d| d.to_string()),
+        true,
+        "a drive strength",
+        |strength| {
+            if DRIVE_STRENGTHS.contains(strength) {
+                Ok(())
+            } else {
+                Err(format!("{} mA is not supported; use 5, 10, 20 or 40", strength))
+            }
+        },
+    )
+}
+
+/// Asks until the answer parses as `what` and passes `check`, or is empty
+/// when `allow_empty` is set.
+fn prompt_parsed<T>(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    default: Option<String>,
+    allow_empty: bool,
+    what: &str,
+    check: impl Fn(&T) -> Result<(), String>,
+) -> Result<Option<T>>
+where
+    T: std::str::FromStr,
+{
+    let mut input = Input::<String>::with_theme(theme)
+        .with_prompt(prompt)
+        .allow_empty(allow_empty)
+        .validate_with(|input: &String| -> Result<(), String> {
+            if input.is_empty() {
+                return Ok(());
+            }
+            let value = input
+                .parse::<T>()
+                .map_err(|_| format!("'{}' is not {}", input, what))?;
+            check(&value)
+        });
+    if let Some(default) = default {
+        input = input.default(default);
+    }
+    let input = input.interact_text()?;
+    Ok(input.parse().ok())
+}
+
+fn prompt_bool(theme: &ColorfulTheme, prompt: &str, default: bool) -> Result<bool> {
+    Ok(Confirm::with_theme(theme)
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Value {
+        serde_yaml_ng::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn saving_keeps_the_files_order_and_sorts_new_entries() {
+        let written = "# yaml-language-server: $schema=./espforge.schema.json\n\nespforge:\n  platform: esp32c3\n  name: blink\nesp32:\n  gpio:\n    red: { pin: 5, direction: output }\n    blue: { pin: 6, direction: output }\n";
+        let original = parse(written);
+        let config = EspforgeConfiguration::parse(
+            &written.replace("    blue:", "    green: { pin: 8, direction: output }\n    amber: { pin: 7, direction: output }\n    blue:"),
+            &LoadOptions::default(),
+        )
+        .unwrap();
+
+        let mut document = serde_yaml_ng::to_value(&config).unwrap();
+        sort_entries(&mut document);
+        keep_order(&mut document, &original);
+
+        let keys = |value: &Value| -> Vec<String> {
+            value
+                .as_mapping()
+                .unwrap()
+                .keys()
+                .map(|k| k.as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(keys(&document["espforge"])[..2], ["platform", "name"]);
+        assert_eq!(
+            keys(&document["esp32"]["gpio"]),
+            ["red", "blue", "amber", "green"]
+        );
+        assert_eq!(
+            leading_comments(written),
+            "# yaml-language-server: $schema=./espforge.schema.json\n\n"
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use crate::config::{Esp32Config, PlatformConfig};
+use anyhow::{Context, Result};
+use include_dir::{Dir, include_dir};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+static METADATA_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/esp32metadata");
+
+/// Per-chip device metadata in the format of esp-hal's `esp-metadata`.
+///
+/// One file per [`PlatformConfig`] is bundled under `esp32metadata/`, with
+/// espforge's own additions in `esp32metadata/espforge/` (see [`ChipOverlay`]).
+#[derive(Debug, Deserialize)]
+pub struct ChipMetadata {
+    pub device: DeviceMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceMetadata {
+    pub name: String,
+    pub arch: String,
+    pub target: String,
+    pub cores: u8,
+    #[serde(default)]
+    pub gpio: GpioMetadata,
+    #[serde(default)]
+    pub i2c_master: PeripheralMetadata,
+    #[serde(default)]
+    pub spi_master: PeripheralMetadata,
+    #[serde(default)]
+    pub uart: PeripheralMetadata,
+    /// Present when the chip has a Wi-Fi radio
+    pub wifi: Option<RadioMetadata>,
+    /// Present when the chip has a Bluetooth/BLE radio
+    pub bt: Option<RadioMetadata>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GpioMetadata {
+    #[serde(default)]
+    pub pins: Vec<PinMetadata>,
+    /// Filled in from the chip's [`ChipOverlay`]
+    #[serde(skip)]
+    pub strapping_pins: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinMetadata {
+    pub pin: u8,
+    /// IO MUX functions, keyed by function number
+    #[serde(default)]
+    pub functions: BTreeMap<u8, String>,
+    /// Analog signals (ADC channels, touch, DAC, USB)
+    #[serde(default, deserialize_with = "signals")]
+    pub analog: Vec<String>,
+    /// Low-power/RTC IO functions
+    #[serde(default, deserialize_with = "signals")]
+    pub rtc: Vec<String>,
+    #[serde(default)]
+    pub input_only: bool,
+    /// Pins reserved for flash/PSRAM on most modules
+    #[serde(default)]
+    pub limited: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PeripheralMetadata {
+    #[serde(default)]
+    pub instances: Vec<InstanceMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstanceMetadata {
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RadioMetadata {}
+
+/// What espforge knows about a chip beyond esp-hal's metadata.
+#[derive(Debug, Default, Deserialize)]
+pub struct ChipOverlay {
+    /// Pins whose level at reset selects the boot mode
+    #[serde(default)]
+    pub strapping_pins: Vec<u8>,
+    /// Pins reserved for flash/PSRAM on most modules
+    #[serde(default)]
+    pub limited: Vec<u8>,
+}
+
+/// Reads `analog`/`rtc` signals either in esp-hal's form, keyed by function
+/// number (`{ 1 = "ADC1_CH0" }`), or as a plain list.
+fn signals<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Signals {
+        Numbered(BTreeMap<String, String>),
+        Listed(Vec<String>),
+    }
+
+    Ok(match Signals::deserialize(deserializer)? {
+        Signals::Numbered(signals) => signals.into_values().collect(),
+        Signals::Listed(signals) => signals,
+    })
+}
+
+/// Loads the bundled metadata for `platform`, with espforge's overlay applied.
+pub fn load_chip_metadata(platform: PlatformConfig) -> Result<ChipMetadata> {
+    let file_name = format!("{}.toml", platform);
+    let content = read_metadata_file(&file_name)?
+        .with_context(|| format!("No chip metadata bundled for {}", platform))?;
+    let mut chip: ChipMetadata = toml::from_str(content)
+        .with_context(|| format!("Failed to parse chip metadata {}", file_name))?;
+
+    let overlay_name = format!("espforge/{}.toml", platform);
+    if let Some(content) = read_metadata_file(&overlay_name)? {
+        let overlay: ChipOverlay = toml::from_str(content)
+            .with_context(|| format!("Failed to parse chip metadata {}", overlay_name))?;
+        chip.apply_overlay(overlay);
+    }
+    Ok(chip)
+}
+
+fn read_metadata_file(file_name: &str) -> Result<Option<&'static str>> {
+    METADATA_DIR
+        .get_file(file_name)
+        .map(|file| {
+            file.contents_utf8()
+                .with_context(|| format!("Metadata file {} is not valid UTF-8", file_name))
+        })
+        .transpose()
+}
+
+impl ChipMetadata {
+    fn apply_overlay(&mut self, overlay: ChipOverlay) {
+        for pin in &mut self.device.gpio.pins {
+            pin.limited |= overlay.limited.contains(&pin.pin);
+        }
+        self.device.gpio.strapping_pins = overlay.strapping_pins;
+    }
+
+    pub fn pin(&self, number: u8) -> Option<&PinMetadata> {
+        self.device.gpio.pins.iter().find(|p| p.pin == number)
+    }
+
+    pub fn is_strapping_pin(&self, number: u8) -> bool {
+        self.device.gpio.strapping_pins.contains(&number)
+    }
+
+    /// `i2c: n` in the config maps to the `i2cN` instance.
+    pub fn has_i2c(&self, bus: u8) -> bool {
+        self.device.i2c_master.has_instance(&format!("i2c{}", bus))
+    }
+
+    /// `spi: n` in the config maps to the `spiN` instance.
+    pub fn has_spi(&self, bus: u8) -> bool {
+        self.device.spi_master.has_instance(&format!("spi{}", bus))
+    }
+
+    /// `uart: n` in the config maps to the `uartN` instance.
+    pub fn has_uart(&self, port: u8) -> bool {
+        self.device.uart.has_instance(&format!("uart{}", port))
+    }
+
+    pub fn has_wifi(&self) -> bool {
+        self.device.wifi.is_some()
+    }
+
+    pub fn has_bt(&self) -> bool {
+        self.device.bt.is_some()
+    }
+
+    /// Whether every pin and bus in `esp32` exists on this chip.
+    ///
+    /// Warnings (strapping or flash pins) do not make a configuration unsupported.
+    pub fn supports(&self, esp32: &Esp32Config) -> bool {
+        let usable = |pin: u8, output: bool| {
+            self.pin(pin)
+                .is_some_and(|p| !(output && p.input_only))
+        };
+
+        esp32
+            .gpio
+            .values()
+            .all(|g| usable(g.pin, g.direction.is_output()))
+            && esp32.spi.values().all(|s| {
+                self.has_spi(s.spi)
+                    && usable(s.sck, true)
+                    && usable(s.mosi, true)
+                    && s.miso.is_none_or(|p| usable(p, false))
+                    && s.cs.is_none_or(|p| usable(p, true))
+            })
+            && esp32
+                .i2c
+                .values()
+                .all(|i| self.has_i2c(i.i2c) && usable(i.sda, true) && usable(i.scl, true))
+            && esp32
+                .uart
+                .values()
+                .all(|u| self.has_uart(u.uart) && usable(u.tx, true) && usable(u.rx, false))
+    }
+}
+
+impl PeripheralMetadata {
+    pub fn has_instance(&self, name: &str) -> bool {
+        self.instances.iter().any(|i| i.name == name)
+    }
+
+    pub fn instance_names(&self) -> Vec<&str> {
+        self.instances.iter().map(|i| i.name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_chip_loads_with_its_overlay() {
+        for platform in PlatformConfig::ALL {
+            let chip = load_chip_metadata(platform).unwrap();
+            assert!(!chip.device.gpio.strapping_pins.is_empty(), "{}", platform);
+            assert!(chip.device.gpio.pins.iter().any(|p| p.limited), "{}", platform);
+        }
+    }
+
+    #[test]
+    fn reads_upstream_signal_maps() {
+        let chip = load_chip_metadata(PlatformConfig::ESP32C3).unwrap();
+        let pin = chip.pin(0).unwrap();
+        assert_eq!(pin.analog, ["ADC1_CH0"]);
+        assert_eq!(pin.rtc, ["RTC_GPIO0"]);
+        assert!(chip.is_strapping_pin(9));
+        assert!(chip.pin(11).unwrap().limited);
+    }
+}
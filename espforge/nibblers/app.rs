@@ -1,8 +1,7 @@
 use crate::{
     config::EspforgeConfiguration,
-    generate::load_manifests,
-    nibblers::{ConfigNibbler, NibblerResult, NibblerStatus},
-    register_nibbler,
+    generate::load_config_manifests,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
     resolver::actions::{ActionResolver, ValidationResult},
 };
 use espforge_macros::auto_register_nibbler;
@@ -22,11 +21,10 @@ impl ConfigNibbler for AppNibbler {
 
     fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
         let mut findings = Vec::new();
-        let mut status = NibblerStatus::Ok;
 
         // We load manifests here to perform semantic validation (checking if methods exist)
         // In a larger system, manifests might be passed in context, but loading here is acceptable.
-        let manifests = load_manifests().map_err(|e| e.to_string())?;
+        let manifests = load_config_manifests(config).map_err(|e| e.to_string())?;
         let resolver = ActionResolver::new();
 
         if let Some(app) = &config.app {
@@ -36,42 +34,39 @@ impl ConfigNibbler for AppNibbler {
             >,
                                       scope: &str| {
                 for (key, value) in action_map {
+                    let path = format!("{}.{}", scope, key);
                     let result = resolver.validate(key, value, config, &manifests);
 
                     match result {
                         ValidationResult::Ok(msg) => {
-                            findings.push(msg);
+                            findings.push(Finding::info("action-valid", path, msg));
                         }
                         ValidationResult::Error(msg) => {
-                            findings.push(format!("Error in {}: {}", scope, msg));
-                            status = NibblerStatus::Error;
+                            findings.push(Finding::error("action-invalid", path, msg));
                         }
                         ValidationResult::Warning(msg) => {
-                            findings.push(format!("Warning in {}: {}", scope, msg));
-                            status = NibblerStatus::Warning;
+                            findings.push(Finding::warning("action-warning", path, msg));
                         }
                         ValidationResult::Ignored => {
-                            findings
-                                .push(format!("Warning in {}: Unknown action '{}'", scope, key));
-                            status = NibblerStatus::Warning;
+                            findings.push(Finding::warning(
+                                "unknown-action",
+                                path,
+                                format!("Unknown action '{}'", key),
+                            ));
                         }
                     }
                 }
             };
 
-            for action in &app.setup {
-                validate_block(action, "app.setup");
+            for (index, action) in app.setup.iter().enumerate() {
+                validate_block(action, &format!("app.setup[{}]", index));
             }
-            for action in &app.loop_fn {
-                validate_block(action, "app.loop");
+            for (index, action) in app.loop_fn.iter().enumerate() {
+                validate_block(action, &format!("app.loop[{}]", index));
             }
         }
 
-        Ok(NibblerResult {
-            nibbler_name: self.name().to_string(),
-            findings,
-            status,
-        })
+        Ok(NibblerResult::from_findings(self.name(), findings))
     }
 }
 //register_nibbler!(AppNibbler);
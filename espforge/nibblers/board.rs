@@ -0,0 +1,52 @@
+use crate::{
+    board::load_board,
+    config::EspforgeConfiguration,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
+};
+use espforge_macros::auto_register_nibbler;
+
+#[derive(Default)]
+#[auto_register_nibbler]
+pub struct BoardNibbler;
+
+impl ConfigNibbler for BoardNibbler {
+    fn name(&self) -> &str {
+        "BoardNibbler"
+    }
+
+    fn priority(&self) -> u8 {
+        1
+    }
+
+    fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
+        let mut findings = Vec::new();
+
+        let Some(name) = &config.espforge.board else {
+            return Ok(NibblerResult::from_findings(self.name(), findings));
+        };
+        let board = load_board(name).map_err(|e| format!("{:#}", e))?;
+        let platform = config.espforge.platform;
+
+        if board.board.chip == platform {
+            findings.push(Finding::info(
+                "board-valid",
+                "espforge.board",
+                format!(
+                    "Board '{}' ({}) uses {}.",
+                    name, board.board.title, platform
+                ),
+            ));
+        } else {
+            findings.push(Finding::warning(
+                "board-chip-mismatch",
+                "espforge.platform",
+                format!(
+                    "Platform {} differs from the {} on board '{}'; its pin labels and bus pins may not apply.",
+                    platform, board.board.chip, name
+                ),
+            ));
+        }
+
+        Ok(NibblerResult::from_findings(self.name(), findings))
+    }
+}
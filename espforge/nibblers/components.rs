@@ -1,7 +1,6 @@
 use crate::{
     config::EspforgeConfiguration,
-    nibblers::{ConfigNibbler, NibblerResult, NibblerStatus},
-    register_nibbler,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
 };
 use espforge_macros::auto_register_nibbler;
 use serde_yaml_ng::Value;
@@ -25,50 +24,38 @@ impl ConfigNibbler for ComponentNibbler {
             None => return Ok(self.empty_result()),
         };
 
-        let (findings, status) = self.validate_components(components, config);
+        let findings = self.validate_components(components, config);
 
-        Ok(NibblerResult {
-            nibbler_name: self.name().to_string(),
-            findings,
-            status,
-        })
+        Ok(NibblerResult::from_findings(self.name(), findings))
     }
 }
 
 impl ComponentNibbler {
     fn empty_result(&self) -> NibblerResult {
-        NibblerResult {
-            nibbler_name: self.name().to_string(),
-            findings: Vec::new(),
-            status: NibblerStatus::Ok,
-        }
+        NibblerResult::from_findings(self.name(), Vec::new())
     }
 
     fn validate_components(
         &self,
         components: &std::collections::HashMap<String, crate::config::ComponentConfig>,
         config: &EspforgeConfiguration,
-    ) -> (Vec<String>, NibblerStatus) {
+    ) -> Vec<Finding> {
         let mut findings = Vec::new();
-        let mut has_errors = false;
 
         for (comp_name, comp_config) in components {
-            findings.push(format!(
-                "Checking component '{}' (using {})",
-                comp_name, comp_config.using
+            findings.push(Finding::info(
+                "component-checked",
+                format!("components.{}", comp_name),
+                format!(
+                    "Checking component '{}' (using {})",
+                    comp_name, comp_config.using
+                ),
             ));
 
-            has_errors |=
-                self.validate_component_references(comp_name, comp_config, config, &mut findings);
+            self.validate_component_references(comp_name, comp_config, config, &mut findings);
         }
 
-        let status = if has_errors {
-            NibblerStatus::Error
-        } else {
-            NibblerStatus::Ok
-        };
-
-        (findings, status)
+        findings
     }
 
     fn validate_component_references(
@@ -76,25 +63,29 @@ impl ComponentNibbler {
         comp_name: &str,
         comp_config: &crate::config::ComponentConfig,
         config: &EspforgeConfiguration,
-        findings: &mut Vec<String>,
-    ) -> bool {
-        let mut has_errors = false;
-
+        findings: &mut Vec<Finding>,
+    ) {
         for (key, value) in &comp_config.with {
             if let Some(ref_name) = self.extract_reference(value) {
+                let path = format!("components.{}.with.{}", comp_name, key);
                 if self.is_valid_reference(ref_name, config) {
-                    findings.push(format!("  Validated reference '${}'", ref_name));
+                    findings.push(Finding::info(
+                        "reference-validated",
+                        path,
+                        format!("Validated reference '${}'", ref_name),
+                    ));
                 } else {
-                    findings.push(format!(
-                        "  Error: Component '{}' references undefined hardware resource '${}' in property '{}'",
-                        comp_name, ref_name, key
+                    findings.push(Finding::error(
+                        "undefined-reference",
+                        path,
+                        format!(
+                            "Component '{}' references undefined hardware resource '${}' in property '{}'",
+                            comp_name, ref_name, key
+                        ),
                     ));
-                    has_errors = true;
                 }
             }
         }
-
-        has_errors
     }
 
     fn extract_reference<'a>(&self, value: &'a Value) -> Option<&'a str> {
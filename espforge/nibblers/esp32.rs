@@ -1,7 +1,7 @@
 use crate::{
-    config::EspforgeConfiguration,
-    nibblers::{ConfigNibbler, NibblerResult, NibblerStatus},
-    register_nibbler,
+    config::{DRIVE_STRENGTHS, EspforgeConfiguration, GpioPinConfig, PinLevel, PlatformConfig},
+    metadata::{ChipMetadata, load_chip_metadata},
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
 };
 use espforge_macros::auto_register_nibbler;
 
@@ -20,19 +20,24 @@ impl ConfigNibbler for HardwareNibbler {
 
     fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
         let mut findings = Vec::new();
-        let mut status = NibblerStatus::Ok;
+        let platform = config.espforge.platform;
+
+        let chip = load_chip_metadata(platform).map_err(|e| e.to_string())?;
 
         if let Some(esp32) = &config.esp32 {
             // Check GPIOs
             for (name, pin_config) in &esp32.gpio {
-                if pin_config.pin > 48 {
-                    findings.push(format!(
-                        "Error: GPIO '{}' uses pin {}, which is out of range.",
-                        name, pin_config.pin
+                let path = format!("esp32.gpio.{}.pin", name);
+                let owner = format!("GPIO '{}'", name);
+                let output = pin_config.direction.is_output();
+                let pin = Pin::new(pin_config.pin, output);
+                self.check_gpio_settings(name, pin_config, &mut findings);
+                if self.check_pin(&chip, platform, pin, &owner, &path, &mut findings) {
+                    findings.push(Finding::info(
+                        "gpio-mapped",
+                        path,
+                        format!("GPIO '{}' mapped to pin {}.", name, pin_config.pin),
                     ));
-                    status = NibblerStatus::Error;
-                } else {
-                    findings.push(format!("GPIO '{}' mapped to pin {}.", name, pin_config.pin));
                 }
             }
 
@@ -42,17 +47,201 @@ impl ConfigNibbler for HardwareNibbler {
                     Some(pin) => pin.to_string(),
                     None => "None".to_string(),
                 };
-                findings.push(format!(
-                    "SPI '{}' configured (SCK:{}, MOSI:{}, MISO:{})",
-                    name, spi_config.sck, spi_config.mosi, miso
+                findings.push(Finding::info(
+                    "spi-configured",
+                    format!("esp32.spi.{}", name),
+                    format!(
+                        "SPI '{}' configured (SCK:{}, MOSI:{}, MISO:{})",
+                        name, spi_config.sck, spi_config.mosi, miso
+                    ),
                 ));
+
+                if !chip.has_spi(spi_config.spi) {
+                    findings.push(Finding::error(
+                        "spi-bus-unavailable",
+                        format!("esp32.spi.{}.spi", name),
+                        format!(
+                            "SPI '{}' uses bus {}, which is not available on {} (available: {}).",
+                            name,
+                            spi_config.spi,
+                            platform,
+                            chip.device.spi_master.instance_names().join(", ")
+                        ),
+                    ));
+                }
+
+                let pins = [
+                    ("sck", Some(spi_config.sck), true),
+                    ("mosi", Some(spi_config.mosi), true),
+                    ("miso", spi_config.miso, false),
+                    ("cs", spi_config.cs, true),
+                ];
+                for (field, pin, output) in pins {
+                    if let Some(pin) = pin {
+                        let pin = Pin::new(pin, output);
+                        let owner = format!("SPI '{}' ({})", name, field);
+                        let path = format!("esp32.spi.{}.{}", name, field);
+                        self.check_pin(&chip, platform, pin, &owner, &path, &mut findings);
+                    }
+                }
+            }
+
+            // Check I2Cs
+            for (name, i2c_config) in &esp32.i2c {
+                if !chip.has_i2c(i2c_config.i2c) {
+                    findings.push(Finding::error(
+                        "i2c-bus-unavailable",
+                        format!("esp32.i2c.{}.i2c", name),
+                        format!(
+                            "I2C '{}' uses bus {}, which is not available on {} (available: {}).",
+                            name,
+                            i2c_config.i2c,
+                            platform,
+                            chip.device.i2c_master.instance_names().join(", ")
+                        ),
+                    ));
+                }
+
+                for (field, pin) in [("sda", i2c_config.sda), ("scl", i2c_config.scl)] {
+                    let pin = Pin::new(pin, true);
+                    let owner = format!("I2C '{}' ({})", name, field);
+                    let path = format!("esp32.i2c.{}.{}", name, field);
+                    self.check_pin(&chip, platform, pin, &owner, &path, &mut findings);
+                }
+            }
+
+            // Check UARTs
+            for (name, uart_config) in &esp32.uart {
+                if !chip.has_uart(uart_config.uart) {
+                    findings.push(Finding::error(
+                        "uart-port-unavailable",
+                        format!("esp32.uart.{}.uart", name),
+                        format!(
+                            "UART '{}' uses port {}, which is not available on {} (available: {}).",
+                            name,
+                            uart_config.uart,
+                            platform,
+                            chip.device.uart.instance_names().join(", ")
+                        ),
+                    ));
+                }
+
+                let pins = [("tx", uart_config.tx, true), ("rx", uart_config.rx, false)];
+                for (field, pin, output) in pins {
+                    let pin = Pin::new(pin, output);
+                    let owner = format!("UART '{}' ({})", name, field);
+                    let path = format!("esp32.uart.{}.{}", name, field);
+                    self.check_pin(&chip, platform, pin, &owner, &path, &mut findings);
+                }
             }
         }
 
-        Ok(NibblerResult {
-            nibbler_name: self.name().to_string(),
-            findings,
-            status,
-        })
+        Ok(NibblerResult::from_findings(self.name(), findings))
+    }
+}
+
+/// A pin as used by one resource; `output` if the resource drives it.
+#[derive(Clone, Copy)]
+struct Pin {
+    number: u8,
+    output: bool,
+}
+
+impl Pin {
+    fn new(number: u8, output: bool) -> Self {
+        Self { number, output }
+    }
+}
+
+impl HardwareNibbler {
+    /// Reports pins that do not exist, cannot be used as wired, or are risky
+    /// to use. Returns false if the pin is unusable.
+    fn check_pin(
+        &self,
+        chip: &ChipMetadata,
+        platform: PlatformConfig,
+        pin: Pin,
+        owner: &str,
+        path: &str,
+        findings: &mut Vec<Finding>,
+    ) -> bool {
+        let Some(metadata) = chip.pin(pin.number) else {
+            findings.push(Finding::error(
+                "pin-not-available",
+                path,
+                format!(
+                    "{} uses GPIO {}, which does not exist on {}.",
+                    owner, pin.number, platform
+                ),
+            ));
+            return false;
+        };
+
+        if pin.output && metadata.input_only {
+            findings.push(Finding::error(
+                "pin-input-only",
+                path,
+                format!(
+                    "{} drives GPIO {}, which is input-only on {}.",
+                    owner, pin.number, platform
+                ),
+            ));
+            return false;
+        }
+
+        if metadata.limited {
+            findings.push(Finding::warning(
+                "pin-limited",
+                path,
+                format!(
+                    "{} uses GPIO {}, which is usually wired to the SPI flash or PSRAM on {}.",
+                    owner, pin.number, platform
+                ),
+            ));
+        }
+        if chip.is_strapping_pin(pin.number) {
+            findings.push(Finding::warning(
+                "pin-strapping",
+                path,
+                format!(
+                    "{} uses GPIO {}, a strapping pin on {}; external pull-ups or pull-downs can change the boot mode.",
+                    owner, pin.number, platform
+                ),
+            ));
+        }
+        true
+    }
+
+    /// Output-only settings on an input, and drive strengths esp-hal lacks.
+    fn check_gpio_settings(&self, name: &str, config: &GpioPinConfig, findings: &mut Vec<Finding>) {
+        if let Some(strength) = config.drive_strength
+            && !DRIVE_STRENGTHS.contains(&strength)
+        {
+            findings.push(Finding::error(
+                "gpio-drive-strength",
+                format!("esp32.gpio.{}.drive_strength", name),
+                format!(
+                    "GPIO '{}' has a drive strength of {} mA; use 5, 10, 20 or 40.",
+                    name, strength
+                ),
+            ));
+        }
+
+        if !config.direction.is_output() {
+            let output_settings = [
+                ("open_drain", config.open_drain),
+                ("drive_strength", config.drive_strength.is_some()),
+                ("initial", config.initial == PinLevel::High),
+            ];
+            for (field, set) in output_settings {
+                if set {
+                    findings.push(Finding::warning(
+                        "gpio-output-setting-on-input",
+                        format!("esp32.gpio.{}.{}", name, field),
+                        format!("GPIO '{}' is an input, so '{}' has no effect.", name, field),
+                    ));
+                }
+            }
+        }
     }
 }
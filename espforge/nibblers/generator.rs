@@ -0,0 +1,131 @@
+use crate::{
+    config::{EspforgeConfiguration, GeneratorBackend, GeneratorOption},
+    metadata::load_chip_metadata,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
+};
+use espforge_macros::auto_register_nibbler;
+
+#[derive(Default)]
+#[auto_register_nibbler]
+pub struct GeneratorNibbler;
+
+impl ConfigNibbler for GeneratorNibbler {
+    fn name(&self) -> &str {
+        "GeneratorNibbler"
+    }
+
+    fn priority(&self) -> u8 {
+        5
+    }
+
+    fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
+        let mut findings = Vec::new();
+        let espforge = &config.espforge;
+        let options = espforge.requested_generator_options();
+        let path = |option: GeneratorOption| option_path(config, option);
+
+        for option in &options {
+            for conflict in option.conflicts() {
+                // Report each pair once
+                if option < conflict && options.contains(conflict) {
+                    findings.push(Finding::error(
+                        "generator-option-conflict",
+                        path(*conflict),
+                        format!(
+                            "Generator options '{}' and '{}' cannot be used together.",
+                            option, conflict
+                        ),
+                    ));
+                }
+            }
+            for required in option.requires() {
+                if !options.contains(required) {
+                    findings.push(Finding::error(
+                        "generator-option-missing",
+                        path(*option),
+                        format!("Generator option '{}' requires '{}'.", option, required),
+                    ));
+                }
+            }
+        }
+
+        if !options.contains(&GeneratorOption::Log) && !options.contains(&GeneratorOption::Defmt) {
+            findings.push(Finding::error(
+                "generator-option-missing",
+                "espforge.generator.options",
+                "The logging global needs either the 'log' or the 'defmt' generator option.",
+            ));
+        }
+        if !options.contains(&GeneratorOption::UnstableHal) {
+            findings.push(Finding::error(
+                "generator-option-missing",
+                "espforge.generator.options",
+                "espforge's platform layer uses unstable esp-hal APIs; add the 'unstable-hal' generator option.",
+            ));
+        }
+
+        let chip = load_chip_metadata(espforge.platform).map_err(|e| e.to_string())?;
+        let radios = [
+            (GeneratorOption::Wifi, chip.has_wifi(), "Wi-Fi"),
+            (GeneratorOption::Ble, chip.has_bt(), "Bluetooth"),
+        ];
+        for (option, available, radio) in radios {
+            if options.contains(&option) && !available {
+                findings.push(Finding::error(
+                    "generator-option-chip",
+                    path(option),
+                    format!(
+                        "Generator option '{}' needs {}, which {} does not have.",
+                        option, radio, espforge.platform
+                    ),
+                ));
+            }
+        }
+
+        let written = espforge
+            .generator
+            .as_ref()
+            .and_then(|g| g.options.clone())
+            .unwrap_or_default();
+        if written.contains(&GeneratorOption::Embassy) && !espforge.enable_async {
+            findings.push(Finding::warning(
+                "generator-embassy-without-async",
+                path(GeneratorOption::Embassy),
+                "The 'embassy' option only adds dependencies; set 'enable_async: true' to generate async code.",
+            ));
+        }
+
+        // Defaults the builtin backend skips (e.g. `vscode`) are not worth a warning
+        if espforge.generator_backend() == GeneratorBackend::Builtin {
+            for option in written.iter().filter(|o| !o.builtin_supported()) {
+                findings.push(Finding::warning(
+                    "generator-option-unsupported",
+                    path(*option),
+                    format!(
+                        "The builtin generator does not implement '{}'; it is ignored. Use the esp-generate backend for it.",
+                        option
+                    ),
+                ));
+            }
+        }
+
+        Ok(NibblerResult::from_findings(self.name(), findings))
+    }
+}
+
+/// Path of `option` in `espforge.generator.options`, or of the section it was
+/// implied by when it is not written out.
+fn option_path(config: &EspforgeConfiguration, option: GeneratorOption) -> String {
+    let written = config
+        .espforge
+        .generator
+        .as_ref()
+        .and_then(|g| g.options.as_ref());
+
+    match written.and_then(|o| o.iter().position(|o| *o == option)) {
+        Some(index) => format!("espforge.generator.options[{}]", index),
+        None if option == GeneratorOption::Embassy => "espforge.enable_async".to_string(),
+        None if written.is_some() => "espforge.generator.options".to_string(),
+        None => "espforge".to_string(),
+    }
+}
@@ -1,5 +1,7 @@
 use crate::config::EspforgeConfiguration;
 use inventory;
+use serde::Serialize;
+use std::fmt;
 
 pub trait ConfigNibbler: Send + Sync {
     fn name(&self) -> &str;
@@ -16,10 +18,93 @@ pub trait ConfigNibbler: Send + Sync {
 #[derive(Debug)]
 pub struct NibblerResult {
     pub nibbler_name: String,
-    pub findings: Vec<String>,
+    pub findings: Vec<Finding>,
     pub status: NibblerStatus,
 }
 
+impl NibblerResult {
+    /// Builds a result whose status is the most severe of its findings.
+    pub fn from_findings(nibbler_name: &str, findings: Vec<Finding>) -> Self {
+        let status = match findings.iter().map(|f| f.severity).max() {
+            Some(Severity::Error) => NibblerStatus::Error,
+            Some(Severity::Warning) => NibblerStatus::Warning,
+            _ => NibblerStatus::Ok,
+        };
+        Self {
+            nibbler_name: nibbler_name.to_string(),
+            findings,
+            status,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single diagnostic produced by a nibbler.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    /// Stable, kebab-case identifier of the check (e.g. `undefined-reference`)
+    pub code: &'static str,
+    /// Dotted YAML path of the offending node (e.g. `esp32.gpio.led.pin`)
+    pub path: String,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn info(code: &'static str, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Info, code, path, message)
+    }
+
+    pub fn warning(code: &'static str, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, path, message)
+    }
+
+    pub fn error(code: &'static str, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, path, message)
+    }
+
+    fn new(
+        severity: Severity,
+        code: &'static str,
+        path: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{} [{}]", self.message, self.code)
+        } else {
+            write!(f, "{}: {} [{}]", self.path, self.message, self.code)
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum NibblerStatus {
     Ok,
@@ -85,7 +170,12 @@ impl NibblerDispatcher {
 }
 
 pub mod app;
+pub mod board;
 pub mod components;
 pub mod esp32;
+pub mod generator;
+pub mod modules;
+pub mod packs;
+pub mod pins;
 pub mod project;
 pub mod template;
@@ -0,0 +1,119 @@
+use crate::{
+    config::EspforgeConfiguration,
+    generate::{ManifestKind, load_local_manifests, load_manifests, local_roots},
+    manifest::ComponentManifest,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
+};
+use espforge_macros::auto_register_nibbler;
+use std::path::Path;
+
+#[derive(Default)]
+#[auto_register_nibbler]
+pub struct LocalModuleNibbler;
+
+impl ConfigNibbler for LocalModuleNibbler {
+    fn name(&self) -> &str {
+        "LocalModuleNibbler"
+    }
+
+    fn priority(&self) -> u8 {
+        2
+    }
+
+    fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
+        let mut findings = Vec::new();
+
+        let locals = match load_local_manifests(config) {
+            Ok(locals) => locals,
+            Err(e) => {
+                findings.push(Finding::error(
+                    "local-module-invalid",
+                    "espforge",
+                    format!("{:#}", e),
+                ));
+                return Ok(NibblerResult::from_findings(self.name(), findings));
+            }
+        };
+        let builtin = load_manifests().map_err(|e| e.to_string())?;
+
+        for (index, local) in locals.iter().enumerate() {
+            let file = local.source.as_deref().unwrap_or(Path::new(""));
+            let path = root_path(config, file);
+
+            let implementation = file.with_extension("rs");
+            if !implementation.is_file() {
+                findings.push(Finding::error(
+                    "local-module-incomplete",
+                    path.clone(),
+                    format!(
+                        "Local manifest {} has no {} next to it.",
+                        file.display(),
+                        implementation.display()
+                    ),
+                ));
+            }
+
+            // Later roots take precedence
+            if let Some(later) = locals[index + 1..].iter().find(|l| collides(l, local)) {
+                findings.push(Finding::warning(
+                    "local-module-shadowed",
+                    path,
+                    format!(
+                        "'{}' from {} is overridden by '{}' from {}.",
+                        local.name,
+                        file.display(),
+                        later.name,
+                        later.source.as_deref().unwrap_or(Path::new("")).display()
+                    ),
+                ));
+            } else if let Some(original) = builtin.values().find(|b| collides(b, local)) {
+                findings.push(Finding::warning(
+                    "local-module-override",
+                    path,
+                    format!(
+                        "Local {} '{}' from {} overrides {}.",
+                        kind_label(local),
+                        local.name,
+                        file.display(),
+                        match &original.source {
+                            Some(source) =>
+                                format!("'{}' from {}", original.name, source.display()),
+                            None => format!("the built-in '{}'", original.name),
+                        }
+                    ),
+                ));
+            }
+        }
+
+        Ok(NibblerResult::from_findings(self.name(), findings))
+    }
+}
+
+/// Two manifests collide when they share a name (`using:`) or a module (file
+/// in the generated crate).
+fn collides(a: &ComponentManifest, b: &ComponentManifest) -> bool {
+    a.name == b.name || a.module == b.module
+}
+
+fn kind_label(manifest: &ComponentManifest) -> &'static str {
+    match ManifestKind::of(manifest) {
+        Some(ManifestKind::Device) => "device",
+        _ => "component",
+    }
+}
+
+/// The `espforge.module_paths` entry `file` was found under, or `espforge` for
+/// the folders next to the configuration.
+fn root_path(config: &EspforgeConfiguration, file: &Path) -> String {
+    let roots = local_roots(config);
+    let paths = &config.espforge.module_paths;
+
+    roots
+        .iter()
+        .take(paths.len())
+        .position(|root| {
+            file.starts_with(root.join("components")) || file.starts_with(root.join("devices"))
+        })
+        .map(|index| format!("espforge.module_paths[{}]", index))
+        .unwrap_or_else(|| "espforge".to_string())
+}
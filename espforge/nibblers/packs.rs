@@ -0,0 +1,74 @@
+use crate::{
+    config::EspforgeConfiguration,
+    generate::load_config_manifests,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
+    pack,
+};
+use espforge_macros::auto_register_nibbler;
+
+#[derive(Default)]
+#[auto_register_nibbler]
+pub struct PackNibbler;
+
+impl ConfigNibbler for PackNibbler {
+    fn name(&self) -> &str {
+        "PackNibbler"
+    }
+
+    fn priority(&self) -> u8 {
+        3
+    }
+
+    fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
+        let mut findings = Vec::new();
+
+        let packs = pack::active_packs().map_err(|e| format!("{:#}", e))?;
+        if packs.is_empty() {
+            return Ok(NibblerResult::from_findings(self.name(), findings));
+        }
+        let manifests = load_config_manifests(config).map_err(|e| format!("{:#}", e))?;
+        let platform = config.espforge.platform;
+
+        let components = config
+            .components
+            .iter()
+            .flatten()
+            .map(|(name, c)| (format!("components.{}.using", name), &c.using));
+        let devices = config
+            .devices
+            .iter()
+            .flatten()
+            .map(|(name, d)| (format!("devices.{}.using", name), &d.using));
+
+        for (path, using) in components.chain(devices) {
+            let Some(source) = manifests.get(using).and_then(|m| m.source.as_ref()) else {
+                continue;
+            };
+            let Some(pack) = packs.iter().find(|p| source.starts_with(&p.root)) else {
+                continue;
+            };
+
+            if pack.supports(platform) {
+                findings.push(Finding::info(
+                    "pack-module",
+                    path,
+                    format!(
+                        "'{}' comes from pack '{}' {}",
+                        using, pack.metadata.name, pack.metadata.version
+                    ),
+                ));
+            } else {
+                findings.push(Finding::warning(
+                    "pack-unsupported-chip",
+                    path,
+                    format!(
+                        "'{}' comes from pack '{}', which does not list {} among its chips.",
+                        using, pack.metadata.name, platform
+                    ),
+                ));
+            }
+        }
+
+        Ok(NibblerResult::from_findings(self.name(), findings))
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    config::EspforgeConfiguration,
+    generate::load_config_manifests,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
+};
+use espforge_macros::auto_register_nibbler;
+use serde_yaml_ng::Value;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Default)]
+#[auto_register_nibbler]
+pub struct PinConflictNibbler;
+
+/// One claim on a physical pin or a `$resource`.
+struct Claim {
+    /// Human-readable owner, e.g. `spi 'display' (sck)`
+    owner: String,
+    /// YAML path of the claim
+    path: String,
+}
+
+impl ConfigNibbler for PinConflictNibbler {
+    fn name(&self) -> &str {
+        "PinConflictNibbler"
+    }
+
+    fn priority(&self) -> u8 {
+        15
+    }
+
+    fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
+        let mut findings = Vec::new();
+
+        for (pin, claims) in self.collect_pins(config) {
+            report_conflicts(&claims, &mut findings, |first, other| {
+                Finding::error(
+                    "pin-conflict",
+                    other.path.clone(),
+                    format!(
+                        "GPIO {} is assigned to both {} and {}.",
+                        pin, first.owner, other.owner
+                    ),
+                )
+            });
+        }
+
+        for ((section, number), claims) in self.collect_peripherals(config) {
+            report_conflicts(&claims, &mut findings, |first, other| {
+                Finding::error(
+                    "peripheral-conflict",
+                    other.path.clone(),
+                    format!(
+                        "{}{} is used by both {} and {}.",
+                        section.to_uppercase(),
+                        number,
+                        first.owner,
+                        other.owner
+                    ),
+                )
+            });
+        }
+
+        let manifests = load_config_manifests(config).map_err(|e| e.to_string())?;
+        for (resource, claims) in self.collect_exclusive_references(config, &manifests) {
+            report_conflicts(&claims, &mut findings, |first, other| {
+                Finding::error(
+                    "resource-conflict",
+                    other.path.clone(),
+                    format!(
+                        "'${}' is claimed by both {} and {}, but it cannot be shared.",
+                        resource, first.owner, other.owner
+                    ),
+                )
+            });
+        }
+
+        Ok(NibblerResult::from_findings(self.name(), findings))
+    }
+}
+
+impl PinConflictNibbler {
+    /// Every physical pin used in the `esp32:` section, keyed by pin number.
+    fn collect_pins(&self, config: &EspforgeConfiguration) -> BTreeMap<u8, Vec<Claim>> {
+        let mut pins: BTreeMap<u8, Vec<Claim>> = BTreeMap::new();
+        let Some(esp32) = &config.esp32 else {
+            return pins;
+        };
+
+        let mut claim = |pin: Option<u8>, section: &str, name: &str, field: &str| {
+            if let Some(pin) = pin {
+                pins.entry(pin).or_default().push(Claim {
+                    owner: format!("{} '{}' ({})", section, name, field),
+                    path: format!("esp32.{}.{}.{}", section, name, field),
+                });
+            }
+        };
+
+        for (name, gpio) in sorted(&esp32.gpio) {
+            claim(Some(gpio.pin), "gpio", name, "pin");
+        }
+        for (name, spi) in sorted(&esp32.spi) {
+            claim(Some(spi.sck), "spi", name, "sck");
+            claim(Some(spi.mosi), "spi", name, "mosi");
+            claim(spi.miso, "spi", name, "miso");
+            claim(spi.cs, "spi", name, "cs");
+        }
+        for (name, i2c) in sorted(&esp32.i2c) {
+            claim(Some(i2c.sda), "i2c", name, "sda");
+            claim(Some(i2c.scl), "i2c", name, "scl");
+        }
+        for (name, uart) in sorted(&esp32.uart) {
+            claim(Some(uart.tx), "uart", name, "tx");
+            claim(Some(uart.rx), "uart", name, "rx");
+        }
+
+        pins
+    }
+
+    /// The bus peripherals the `esp32:` section uses, keyed by section and
+    /// peripheral number (e.g. `("i2c", 0)`).
+    fn collect_peripherals(
+        &self,
+        config: &EspforgeConfiguration,
+    ) -> BTreeMap<(&'static str, u8), Vec<Claim>> {
+        let mut peripherals: BTreeMap<(&'static str, u8), Vec<Claim>> = BTreeMap::new();
+        let Some(esp32) = &config.esp32 else {
+            return peripherals;
+        };
+
+        let mut claim = |section: &'static str, name: &str, number: u8| {
+            peripherals.entry((section, number)).or_default().push(Claim {
+                owner: format!("{} '{}'", section, name),
+                path: format!("esp32.{}.{}.{}", section, name, section),
+            });
+        };
+
+        for (name, i2c) in sorted(&esp32.i2c) {
+            claim("i2c", name, i2c.i2c);
+        }
+        for (name, spi) in sorted(&esp32.spi) {
+            claim("spi", name, spi.spi);
+        }
+        for (name, uart) in sorted(&esp32.uart) {
+            claim("uart", name, uart.uart);
+        }
+
+        peripherals
+    }
+
+    /// `$resource` references from component and device parameters whose
+    /// type cannot be shared, keyed by resource name.
+    fn collect_exclusive_references(
+        &self,
+        config: &EspforgeConfiguration,
+        manifests: &HashMap<String, crate::manifest::ComponentManifest>,
+    ) -> BTreeMap<String, Vec<Claim>> {
+        let mut references: BTreeMap<String, Vec<Claim>> = BTreeMap::new();
+
+        let components = config
+            .components
+            .iter()
+            .flatten()
+            .map(|(n, c)| ("components", "component", n, &c.using, &c.with));
+        let devices = config
+            .devices
+            .iter()
+            .flatten()
+            .map(|(n, d)| ("devices", "device", n, &d.using, &d.with));
+
+        let mut instances: Vec<_> = components.chain(devices).collect();
+        instances.sort_by_key(|(section, _, name, _, _)| (*section, *name));
+
+        for (section, kind, name, using, with) in instances {
+            let Some(manifest) = manifests.get(using) else {
+                continue;
+            };
+
+            for param in &manifest.parameters {
+                if param.param_type.is_shareable() {
+                    continue;
+                }
+                let Some(resource) = with
+                    .get(&param.name)
+                    .and_then(Value::as_str)
+                    .and_then(|s| s.strip_prefix('$'))
+                else {
+                    continue;
+                };
+
+                references
+                    .entry(resource.to_string())
+                    .or_default()
+                    .push(Claim {
+                        owner: format!("{} '{}'", kind, name),
+                        path: format!("{}.{}.with.{}", section, name, param.name),
+                    });
+            }
+        }
+
+        references
+    }
+}
+
+/// Reports every claim after the first one as a conflict with the first.
+fn report_conflicts<F>(claims: &[Claim], findings: &mut Vec<Finding>, conflict: F)
+where
+    F: Fn(&Claim, &Claim) -> Finding,
+{
+    if let Some((first, others)) = claims.split_first() {
+        for other in others {
+            findings.push(conflict(first, other));
+        }
+    }
+}
+
+fn sorted<T>(map: &HashMap<String, T>) -> Vec<(&String, &T)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(name, _)| *name);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The (code, path) of every finding for `yaml`.
+    fn conflicts(yaml: &str) -> Vec<(&'static str, String)> {
+        let config: EspforgeConfiguration = serde_yaml_ng::from_str(yaml).unwrap();
+        PinConflictNibbler
+            .process(&config)
+            .unwrap()
+            .findings
+            .into_iter()
+            .map(|f| (f.code, f.path))
+            .collect()
+    }
+
+    #[test]
+    fn two_gpios_on_one_pin() {
+        let yaml = r#"
+            espforge: { name: pins, platform: esp32c3 }
+            esp32:
+              gpio:
+                led: { pin: 4, direction: output }
+                button: { pin: 4, direction: input }
+        "#;
+
+        assert_eq!(
+            conflicts(yaml),
+            [("pin-conflict", "esp32.gpio.led.pin".to_string())]
+        );
+    }
+
+    #[test]
+    fn bus_pin_reused_as_gpio() {
+        let yaml = r#"
+            espforge: { name: pins, platform: esp32c3 }
+            esp32:
+              gpio:
+                led: { pin: 6, direction: output }
+              i2c:
+                i2c0: { i2c: 0, sda: 6, scl: 7 }
+        "#;
+
+        assert_eq!(
+            conflicts(yaml),
+            [("pin-conflict", "esp32.i2c.i2c0.sda".to_string())]
+        );
+    }
+
+    #[test]
+    fn devices_may_share_a_bus() {
+        let yaml = r#"
+            espforge: { name: pins, platform: esp32c3 }
+            esp32:
+              i2c:
+                i2c0: { i2c: 0, sda: 6, scl: 7 }
+            components:
+              bus: { using: i2c, with: { i2c: $i2c0 } }
+            devices:
+              left: { using: ssd1306, with: { component: $bus, address: 60 } }
+              right: { using: ssd1306, with: { component: $bus, address: 61 } }
+        "#;
+
+        assert!(conflicts(yaml).is_empty());
+    }
+
+    #[test]
+    fn bus_instances_cannot_be_claimed_twice() {
+        let yaml = r#"
+            espforge: { name: pins, platform: esp32c3 }
+            esp32:
+              i2c:
+                i2c0: { i2c: 0, sda: 6, scl: 7 }
+              uart:
+                uart1: { uart: 1, tx: 4, rx: 5 }
+            components:
+              bus_a: { using: i2c, with: { i2c: $i2c0 } }
+              bus_b: { using: i2c, with: { i2c: $i2c0 } }
+              console: { using: uart, with: { uart: $uart1, baud: 9600 } }
+              modem: { using: uart, with: { uart: $uart1, baud: 115200 } }
+        "#;
+
+        assert_eq!(
+            conflicts(yaml),
+            [
+                ("resource-conflict", "components.bus_b.with.i2c".to_string()),
+                ("resource-conflict", "components.modem.with.uart".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn bus_entries_on_one_peripheral() {
+        let yaml = r#"
+            espforge: { name: pins, platform: esp32c3 }
+            esp32:
+              i2c:
+                sensors: { i2c: 0, sda: 6, scl: 7 }
+                display: { i2c: 0, sda: 8, scl: 9 }
+              uart:
+                console: { uart: 0, tx: 21, rx: 20 }
+                modem: { uart: 1, tx: 4, rx: 5 }
+        "#;
+
+        assert_eq!(
+            conflicts(yaml),
+            [("peripheral-conflict", "esp32.i2c.sensors.i2c".to_string())]
+        );
+    }
+}
@@ -1,7 +1,6 @@
 use crate::{
     config::EspforgeConfiguration,
-    nibblers::{ConfigNibbler, NibblerResult, NibblerStatus},
-    register_nibbler,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
 };
 use espforge_macros::auto_register_nibbler;
 
@@ -20,28 +19,29 @@ impl ConfigNibbler for ProjectNameNibbler {
 
     fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
         let mut findings = Vec::new();
-        let mut status = NibblerStatus::Ok;
 
         let name = config.get_name();
 
         if name.contains(' ') {
-            findings.push(format!("Error: Project name '{}' contains spaces.", name));
-            status = NibblerStatus::Error;
+            findings.push(Finding::error(
+                "project-name-spaces",
+                "espforge.name",
+                format!("Project name '{}' contains spaces.", name),
+            ));
         } else if name.chars().any(|c| !c.is_alphanumeric() && c != '_') {
-            findings.push(format!(
-                "Warning: Project name '{}' contains special characters.",
-                name
+            findings.push(Finding::warning(
+                "project-name-characters",
+                "espforge.name",
+                format!("Project name '{}' contains special characters.", name),
             ));
-            status = NibblerStatus::Warning;
         } else {
-            findings.push(format!("Project name '{}' is valid.", name));
+            findings.push(Finding::info(
+                "project-name-valid",
+                "espforge.name",
+                format!("Project name '{}' is valid.", name),
+            ));
         }
 
-        Ok(NibblerResult {
-            nibbler_name: self.name().to_string(),
-            findings,
-            status,
-        })
+        Ok(NibblerResult::from_findings(self.name(), findings))
     }
 }
-register_nibbler!(ProjectNameNibbler);
@@ -1,7 +1,6 @@
 use crate::{
     config::EspforgeConfiguration,
-    nibblers::{ConfigNibbler, NibblerResult, NibblerStatus},
-    register_nibbler,
+    nibblers::{ConfigNibbler, Finding, NibblerResult},
 };
 use espforge_macros::auto_register_nibbler;
 
@@ -20,26 +19,33 @@ impl ConfigNibbler for TemplateNibbler {
 
     fn process(&self, config: &EspforgeConfiguration) -> Result<NibblerResult, String> {
         let mut findings = Vec::new();
-        let status = NibblerStatus::Ok;
 
         if let Some(template) = config.get_template() {
-            findings.push(format!("Using template: {}", template));
+            findings.push(Finding::info(
+                "template-selected",
+                "example.name",
+                format!("Using template: {}", template),
+            ));
 
             // Example specific check
             if template == "blink"
                 && let Some(ex) = &config.example
                 && !ex.example_properties.contains_key("blink_rate_ms")
             {
-                findings.push("Note: 'blink_rate_ms' not set. Using template default.".to_string());
+                findings.push(Finding::info(
+                    "template-property-default",
+                    "example.blink_rate_ms",
+                    "'blink_rate_ms' not set. Using template default.",
+                ));
             }
         } else {
-            findings.push("No example template specified. Using minimal default.".to_string());
+            findings.push(Finding::info(
+                "template-none",
+                "example",
+                "No example template specified. Using minimal default.",
+            ));
         }
 
-        Ok(NibblerResult {
-            nibbler_name: self.name().to_string(),
-            findings,
-            status,
-        })
+        Ok(NibblerResult::from_findings(self.name(), findings))
     }
 }
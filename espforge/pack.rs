@@ -0,0 +1,341 @@
+use crate::config::PlatformConfig;
+use anyhow::{Context, Result, anyhow, bail};
+use flate2::read::GzDecoder;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The metadata file at the root of a pack.
+pub const PACK_FILE: &str = "pack.toml";
+
+/// Overrides the user data directory packs are installed into.
+pub const DATA_DIR_ENV: &str = "ESPFORGE_DATA_DIR";
+
+/// Contents of `pack.toml`.
+///
+/// A pack is a directory laid out like espforge itself:
+///
+/// ```text
+/// pack.toml
+/// components/<name>.ron, <name>.rs
+/// devices/<name>/device.ron, device.rs, Cargo.toml.tera
+/// templates/<category>/<example>/example.yaml, app.ruchy, ...
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PackMetadata {
+    pub name: String,
+    pub version: Version,
+    #[serde(default)]
+    pub description: String,
+    /// espforge versions the pack works with, e.g. `^0.1`
+    #[serde(default = "any_version")]
+    pub espforge: VersionReq,
+    /// Chips the pack's drivers support; empty for all of them.
+    #[serde(default)]
+    pub chips: Vec<PlatformConfig>,
+}
+
+fn any_version() -> VersionReq {
+    VersionReq::STAR
+}
+
+/// A pack on disk.
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub metadata: PackMetadata,
+    pub root: PathBuf,
+}
+
+impl Pack {
+    /// Reads the pack whose `pack.toml` is in `root`.
+    pub fn load(root: &Path) -> Result<Pack> {
+        let file = root.join(PACK_FILE);
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let metadata: PackMetadata = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", file.display()))?;
+
+        let valid = !metadata.name.is_empty()
+            && metadata
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            bail!(
+                "Invalid pack name '{}' in {}: use letters, digits, '-' and '_'",
+                metadata.name,
+                file.display()
+            );
+        }
+
+        Ok(Pack {
+            metadata,
+            root: root.to_path_buf(),
+        })
+    }
+
+    /// Whether the pack works with this version of espforge.
+    pub fn is_compatible(&self) -> bool {
+        self.metadata.espforge.matches(&espforge_version())
+    }
+
+    pub fn supports(&self, platform: PlatformConfig) -> bool {
+        self.metadata.chips.is_empty() || self.metadata.chips.contains(&platform)
+    }
+
+    /// The pack's examples, by category.
+    pub fn examples(&self) -> Result<BTreeMap<String, Vec<String>>> {
+        let mut map = BTreeMap::new();
+        for category in subdirs(&self.root.join("templates"))? {
+            let examples: Vec<String> = subdirs(&category)?
+                .iter()
+                .filter(|dir| dir.join("example.yaml").is_file())
+                .map(|dir| file_name(dir))
+                .collect();
+            if !examples.is_empty() {
+                map.insert(file_name(&category), examples);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Directory of the example called `name`, if the pack has one.
+    pub fn find_example(&self, name: &str) -> Option<PathBuf> {
+        subdirs(&self.root.join("templates"))
+            .ok()?
+            .into_iter()
+            .map(|category| category.join(name))
+            .find(|dir| dir.join("example.yaml").is_file())
+    }
+}
+
+fn espforge_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("crate version is valid semver")
+}
+
+/// Where espforge keeps user data such as installed packs: `$ESPFORGE_DATA_DIR`,
+/// or `espforge` in the platform's data directory.
+pub fn data_dir() -> Option<PathBuf> {
+    match std::env::var_os(DATA_DIR_ENV) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => dirs::data_dir().map(|dir| dir.join("espforge")),
+    }
+}
+
+pub fn packs_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("packs"))
+}
+
+static SKIPPED_PACKS: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Every installed pack, sorted by name, compatible or not.
+///
+/// A pack that fails to load is skipped with a warning, so one broken pack
+/// does not stop every command.
+pub fn installed_packs() -> Result<Vec<Pack>> {
+    let Some(dir) = packs_dir() else {
+        return Ok(Vec::new());
+    };
+    Ok(subdirs(&dir)?
+        .iter()
+        // Skips the staging copy of a pack being installed
+        .filter(|root| !file_name(root).starts_with('.'))
+        .filter(|root| root.join(PACK_FILE).is_file())
+        .filter_map(|root| match Pack::load(root) {
+            Ok(pack) => Some(pack),
+            Err(e) => {
+                // Packs are listed several times per command; warn once
+                let mut warned = SKIPPED_PACKS.lock().unwrap_or_else(|e| e.into_inner());
+                if warned.insert(root.clone()) {
+                    eprintln!("Warning: skipping pack at {}: {:#}", root.display(), e);
+                }
+                None
+            }
+        })
+        .collect())
+}
+
+/// The installed packs that work with this version of espforge; their
+/// contents are merged with the embedded components, devices and examples.
+pub fn active_packs() -> Result<Vec<Pack>> {
+    Ok(installed_packs()?
+        .into_iter()
+        .filter(Pack::is_compatible)
+        .collect())
+}
+
+/// Finds the example called `name` in the active packs.
+pub fn find_example(name: &str) -> Result<Option<PathBuf>> {
+    Ok(active_packs()?
+        .iter()
+        .find_map(|pack| pack.find_example(name)))
+}
+
+/// What [`install`] did.
+#[derive(Debug)]
+pub struct InstallOutcome {
+    pub pack: Pack,
+    /// Version of the same pack that was replaced, if any.
+    pub replaced: Option<Version>,
+}
+
+/// Installs the pack in `source` (a directory, `.tar`, `.tar.gz` or `.tgz`)
+/// into [`packs_dir`], replacing an installed pack of the same name.
+pub fn install(source: &Path) -> Result<InstallOutcome> {
+    let packs = packs_dir().ok_or_else(|| {
+        anyhow!(
+            "No user data directory found; set {} to choose one",
+            DATA_DIR_ENV
+        )
+    })?;
+
+    if source.is_dir() {
+        return install_from(source, &packs);
+    }
+
+    let scratch = std::env::temp_dir().join(format!("espforge-pack-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&scratch);
+    let result = unpack(source, &scratch).and_then(|()| {
+        let root = if scratch.join(PACK_FILE).is_file() {
+            scratch.clone()
+        } else {
+            // Archives usually wrap the pack in a single top-level directory
+            match subdirs(&scratch)?.as_slice() {
+                [dir] if dir.join(PACK_FILE).is_file() => dir.clone(),
+                _ => bail!("{} has no {}", source.display(), PACK_FILE),
+            }
+        };
+        install_from(&root, &packs)
+    });
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+fn install_from(root: &Path, packs: &Path) -> Result<InstallOutcome> {
+    let pack = Pack::load(root)?;
+    if !pack.is_compatible() {
+        bail!(
+            "Pack '{}' {} requires espforge {}, this is {}",
+            pack.metadata.name,
+            pack.metadata.version,
+            pack.metadata.espforge,
+            espforge_version()
+        );
+    }
+
+    let dest = packs.join(&pack.metadata.name);
+    let source = root
+        .canonicalize()
+        .with_context(|| format!("Failed to read {}", root.display()))?;
+    if let Ok(installed) = dest.canonicalize()
+        && source.starts_with(&installed)
+    {
+        bail!(
+            "{} is the installed copy of pack '{}'; nothing to install",
+            root.display(),
+            pack.metadata.name
+        );
+    }
+    if let Ok(packs) = packs.canonicalize()
+        && packs.starts_with(&source)
+    {
+        bail!(
+            "{} contains the packs directory {}; move the pack elsewhere to install it",
+            root.display(),
+            packs.display()
+        );
+    }
+
+    // Copied next to the destination first, so a failed copy leaves the
+    // installed version in place
+    let staging = packs.join(format!(".{}.partial", pack.metadata.name));
+    let _ = fs::remove_dir_all(&staging);
+    if let Err(e) = copy_dir(root, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    let replaced = Pack::load(&dest).ok().map(|old| old.metadata.version);
+    if dest.exists() {
+        fs::remove_dir_all(&dest)
+            .with_context(|| format!("Failed to remove {}", dest.display()))?;
+    }
+    fs::rename(&staging, &dest)
+        .with_context(|| format!("Failed to move {} to {}", staging.display(), dest.display()))?;
+
+    Ok(InstallOutcome {
+        pack: Pack::load(&dest)?,
+        replaced,
+    })
+}
+
+fn unpack(archive: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let name = file_name(archive);
+    let reader: Box<dyn Read> = if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Box::new(GzDecoder::new(file))
+    } else if name.ends_with(".tar") {
+        Box::new(file)
+    } else {
+        bail!(
+            "{} is not a directory, .tar, .tar.gz or .tgz",
+            archive.display()
+        );
+    };
+
+    fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create directory: {}", dest.display()))?;
+    tar::Archive::new(reader)
+        .unpack(dest)
+        .with_context(|| format!("Failed to unpack {}", archive.display()))
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create directory: {}", dest.display()))?;
+    for entry in
+        fs::read_dir(src).with_context(|| format!("Failed to read directory: {}", src.display()))?
+    {
+        let path = entry?.path();
+        let target = dest.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            if path.file_name().is_some_and(|n| n == ".git") {
+                continue;
+            }
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)
+                .with_context(|| format!("Failed to copy {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Subdirectories of `dir`, sorted; none if it does not exist.
+fn subdirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut dirs = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
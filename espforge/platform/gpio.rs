@@ -1,10 +1,65 @@
 use esp_hal::{
-    gpio::{AnyPin, Input, InputConfig, Level, Output, OutputConfig, Pull},
+    gpio::{
+        AnyPin, DriveMode, DriveStrength, Flex, Input, InputConfig, Level, OutputConfig, Pull,
+    },
 };
 
+/// Electrical settings of a GPIO, from its `esp32.gpio` entry
+#[derive(Clone, Copy, Default)]
+pub struct PinSettings {
+    pub pull_up: bool,
+    pub pull_down: bool,
+    /// Only drive the pin low and let it float high
+    pub open_drain: bool,
+    /// Drive strength in mA: 5, 10, 20 or 40 (0 keeps the default, 20)
+    pub drive_strength: u8,
+    /// Logical level of an output before the first write
+    pub initial_high: bool,
+    /// Active-low: a logical high is a low level on the pin
+    pub inverted: bool,
+    /// Keep the input enabled on an output so it can be read back
+    pub input_output: bool,
+}
+
+impl PinSettings {
+    fn pull(&self) -> Pull {
+        if self.pull_up {
+            Pull::Up
+        } else if self.pull_down {
+            Pull::Down
+        } else {
+            Pull::None
+        }
+    }
+
+    fn output_config(&self) -> OutputConfig {
+        let drive_mode = if self.open_drain {
+            DriveMode::OpenDrain
+        } else {
+            DriveMode::PushPull
+        };
+        let drive_strength = match self.drive_strength {
+            5 => DriveStrength::_5mA,
+            10 => DriveStrength::_10mA,
+            40 => DriveStrength::_40mA,
+            _ => DriveStrength::_20mA,
+        };
+        OutputConfig::default()
+            .with_drive_mode(drive_mode)
+            .with_drive_strength(drive_strength)
+            .with_pull(self.pull())
+    }
+
+    /// The level on the pin for a logical level
+    fn level(&self, high: bool) -> Level {
+        Level::from(high != self.inverted)
+    }
+}
+
 /// User-friendly GPIO output wrapper
 pub struct GPIOOutput {
-    output: Output<'static>,
+    pin: Flex<'static>,
+    inverted: bool,
 }
 
 impl GPIOOutput {
@@ -16,40 +71,66 @@ impl GPIOOutput {
     /// # Panics
     /// Panics if the pin number is invalid or already in use
     pub fn new(pin_number: u8) -> Self {
+        // Default to push-pull output with 20mA drive strength, starting low
+        Self::with_settings(pin_number, PinSettings::default())
+    }
+
+    /// Creates a new GPIO output with the drive mode, strength, initial level
+    /// and polarity in `settings`
+    ///
+    /// # Panics
+    /// Panics if the pin number is invalid or already in use
+    pub fn with_settings(pin_number: u8, settings: PinSettings) -> Self {
         // Safety: We ensure only one instance exists by consuming the AnyPin
         let any_pin = unsafe { AnyPin::steal(pin_number) };
 
-        // Default to push-pull output with 20mA drive strength
-        let config = OutputConfig::default();
-        let output = Output::new(any_pin, Level::Low, config);
-
-        GPIOOutput { output }
+        let mut pin = Flex::new(any_pin);
+        pin.apply_output_config(&settings.output_config());
+        // Set the level before enabling the output so the pin never glitches
+        pin.set_level(settings.level(settings.initial_high));
+        pin.set_output_enable(true);
+        if settings.input_output {
+            pin.apply_input_config(&InputConfig::default().with_pull(settings.pull()));
+            pin.set_input_enable(true);
+        }
+
+        GPIOOutput {
+            pin,
+            inverted: settings.inverted,
+        }
     }
 
-    /// Sets the output level high
+    /// Sets the output level high (low on the pin when inverted)
     pub fn set_high(&mut self) {
-        self.output.set_high();
+        self.pin.set_level(Level::from(!self.inverted));
     }
 
-    /// Sets the output level low
+    /// Sets the output level low (high on the pin when inverted)
     pub fn set_low(&mut self) {
-        self.output.set_low();
+        self.pin.set_level(Level::from(self.inverted));
     }
 
     /// Toggles the output level
     pub fn toggle(&mut self) {
-        self.output.toggle();
+        self.pin.toggle();
     }
 
     /// Gets the current output level
     pub fn is_high(&self) -> bool {
-        self.output.is_set_high()
+        self.pin.is_set_high() != self.inverted
+    }
+
+    /// Reads the level on the pin, e.g. an open-drain line another device
+    /// pulls low. Needs `direction: input_output`.
+    pub fn read_high(&self) -> bool {
+        self.pin.is_high() != self.inverted
     }
 }
 
 
 pub struct GPIOInput {
     input: Input<'static>,
+    inverted: bool,
 }
 
 impl GPIOInput {
@@ -62,55 +143,85 @@ impl GPIOInput {
     /// # Panics
     /// Panics if the pin number is invalid or already in use
     pub fn new(pin_number: u8, pull_up: bool, pull_down: bool) -> Self {
+        Self::with_settings(
+            pin_number,
+            PinSettings {
+                pull_up,
+                pull_down,
+                ..PinSettings::default()
+            },
+        )
+    }
+
+    /// Creates a new GPIO input with the pulls and polarity in `settings`
+    ///
+    /// # Panics
+    /// Panics if the pin number is invalid or already in use
+    pub fn with_settings(pin_number: u8, settings: PinSettings) -> Self {
         // Safety: We ensure only one instance exists by consuming the AnyPin
         let any_pin = unsafe { AnyPin::steal(pin_number) };
 
-        let pull = if pull_up {
-            Pull::Up
-        } else if pull_down {
-            Pull::Down
-        } else {
-            Pull::None
-        };
-
-        let config = InputConfig::default().with_pull(pull);
+        let config = InputConfig::default().with_pull(settings.pull());
         let input = Input::new(any_pin, config);
 
-        GPIOInput { input }
+        GPIOInput {
+            input,
+            inverted: settings.inverted,
+        }
     }
 
-    /// Reads the current input level
+    /// Reads the current input level (inverted for active-low inputs)
     pub fn read(&self) -> Level {
-        self.input.level()
+        if self.inverted {
+            !self.input.level()
+        } else {
+            self.input.level()
+        }
     }
 
     /// Returns true if the input is high
     pub fn is_high(&self) -> bool {
-        self.input.is_high()
+        self.input.is_high() != self.inverted
     }
 
     /// Returns true if the input is low
     pub fn is_low(&self) -> bool {
-        self.input.is_low()
+        self.input.is_low() != self.inverted
     }
 
-     pub async fn wait_for_low(&mut self) {
-        self.input.wait_for_low().await;
+    /// Asynchronously wait for the input to go low
+    pub async fn wait_for_low(&mut self) {
+        if self.inverted {
+            self.input.wait_for_high().await;
+        } else {
+            self.input.wait_for_low().await;
+        }
     }
 
-    /// Asynchronously wait for the pin to go high
+    /// Asynchronously wait for the input to go high
     pub async fn wait_for_high(&mut self) {
-        self.input.wait_for_high().await;
+        if self.inverted {
+            self.input.wait_for_low().await;
+        } else {
+            self.input.wait_for_high().await;
+        }
     }
 
-    /// Asynchronously wait for a rising edge
+    /// Asynchronously wait for a rising edge of the input
     pub async fn wait_for_rising_edge(&mut self) {
-        self.input.wait_for_rising_edge().await;
+        if self.inverted {
+            self.input.wait_for_falling_edge().await;
+        } else {
+            self.input.wait_for_rising_edge().await;
+        }
     }
 
-    /// Asynchronously wait for a falling edge
+    /// Asynchronously wait for a falling edge of the input
     pub async fn wait_for_falling_edge(&mut self) {
-        self.input.wait_for_falling_edge().await;
+        if self.inverted {
+            self.input.wait_for_rising_edge().await;
+        } else {
+            self.input.wait_for_falling_edge().await;
+        }
     }
 }
-
@@ -1,4 +0,0 @@
-pub mod gpio;
-pub mod i2c;
-pub mod spi;
-pub mod uart;
\ No newline at end of file
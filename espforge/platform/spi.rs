@@ -8,7 +8,7 @@ use esp_hal::{
     time::Rate,
     Blocking,
 };
-
+use embedded_hal::spi::{ErrorType, SpiBus};
 
 pub struct SPIMaster {
     spi: Spi<'static, Blocking>,
@@ -88,3 +88,30 @@ impl SPIMaster {
     }
 }
 
+// --- embedded-hal Implementation ---
+
+impl ErrorType for SPIMaster {
+    type Error = esp_hal::spi::Error;
+}
+
+impl SpiBus for SPIMaster {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBus::read(&mut self.spi, words)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        SpiBus::write(&mut self.spi, words)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        SpiBus::transfer(&mut self.spi, read, write)
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        SpiBus::transfer_in_place(&mut self.spi, words)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        SpiBus::flush(&mut self.spi)
+    }
+}
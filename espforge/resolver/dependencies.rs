@@ -0,0 +1,275 @@
+use crate::config::EspforgeConfiguration;
+use crate::manifest::{ComponentManifest, ParameterType};
+use crate::resolver::ConfigPath;
+use anyhow::{Context, Result, anyhow};
+use serde_yaml_ng::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// A `components:` or `devices:` entry. Orders components before devices,
+/// then by name, which is the tie-break between independent instances.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InstanceId {
+    Component(String),
+    Device(String),
+}
+
+impl InstanceId {
+    fn path(&self) -> String {
+        match self {
+            InstanceId::Component(name) => format!("components.{}", name),
+            InstanceId::Device(name) => format!("devices.{}", name),
+        }
+    }
+}
+
+/// Orders every component and device so that each comes after the
+/// components it references through `*ComponentRef` parameters.
+///
+/// Fails on references to undefined components, to components that do not
+/// own a bus of the expected kind, and on reference cycles.
+pub fn initialization_order(
+    config: &EspforgeConfiguration,
+    manifests: &HashMap<String, ComponentManifest>,
+) -> Result<Vec<InstanceId>> {
+    let components = config
+        .components
+        .iter()
+        .flatten()
+        .map(|(name, c)| (InstanceId::Component(name.clone()), &c.using, &c.with));
+    let devices = config
+        .devices
+        .iter()
+        .flatten()
+        .map(|(name, d)| (InstanceId::Device(name.clone()), &d.using, &d.with));
+
+    // instance -> the components it needs
+    let mut dependencies: BTreeMap<InstanceId, BTreeSet<InstanceId>> = BTreeMap::new();
+
+    for (id, using, with) in components.chain(devices) {
+        let mut needs = BTreeSet::new();
+
+        if let Some(manifest) = manifests.get(using) {
+            for param in &manifest.parameters {
+                let Some(bus) = param.param_type.bus_ref() else {
+                    continue;
+                };
+                let Some(target) = with
+                    .get(&param.name)
+                    .and_then(Value::as_str)
+                    .and_then(|s| s.strip_prefix('$'))
+                else {
+                    continue;
+                };
+                let path = format!("{}.with.{}", id.path(), param.name);
+
+                let Some(component) = config.components.as_ref().and_then(|c| c.get(target)) else {
+                    return Err(anyhow!(
+                        "'{}' references undefined component '${}'",
+                        id.path(),
+                        target
+                    ))
+                    .context(ConfigPath(path));
+                };
+
+                // Only components that own a bus of the right kind can be shared
+                let owns_bus = manifests
+                    .get(&component.using)
+                    .is_some_and(|m| m.parameters.iter().any(|p| p.param_type == bus));
+                if !owns_bus {
+                    let kind = if bus == ParameterType::I2cRef {
+                        "I2C"
+                    } else {
+                        "SPI"
+                    };
+                    return Err(anyhow!(
+                        "'{}' expects an {} bus component, but '${}' is a '{}' component",
+                        id.path(),
+                        kind,
+                        target,
+                        component.using
+                    ))
+                    .context(ConfigPath(path));
+                }
+                needs.insert(InstanceId::Component(target.to_string()));
+            }
+        }
+
+        dependencies.insert(id, needs);
+    }
+
+    topological_sort(dependencies)
+}
+
+/// Kahn's algorithm, always emitting the smallest ready instance first.
+fn topological_sort(
+    dependencies: BTreeMap<InstanceId, BTreeSet<InstanceId>>,
+) -> Result<Vec<InstanceId>> {
+    let mut remaining: BTreeMap<InstanceId, usize> = dependencies
+        .iter()
+        .map(|(id, needs)| (id.clone(), needs.len()))
+        .collect();
+    let mut dependents: BTreeMap<&InstanceId, Vec<&InstanceId>> = BTreeMap::new();
+    for (id, needs) in &dependencies {
+        for need in needs {
+            dependents.entry(need).or_default().push(id);
+        }
+    }
+
+    let mut ready: BTreeSet<InstanceId> = remaining
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(id, _)| id.clone())
+        .collect();
+    let mut order = Vec::with_capacity(dependencies.len());
+
+    while let Some(id) = ready.pop_first() {
+        remaining.remove(&id);
+        for dependent in dependents.get(&id).into_iter().flatten() {
+            if let Some(count) = remaining.get_mut(*dependent) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.insert((*dependent).clone());
+                }
+            }
+        }
+        order.push(id);
+    }
+
+    if let Some(first) = remaining.keys().next() {
+        let cycle: Vec<String> = remaining.keys().map(InstanceId::path).collect();
+        return Err(anyhow!("Reference cycle between {}", cycle.join(", ")))
+            .context(ConfigPath(first.path()));
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, parameters: &[(&str, ParameterType)]) -> (String, ComponentManifest) {
+        let manifest = ComponentManifest {
+            name: name.to_string(),
+            requires: Vec::new(),
+            uses: Vec::new(),
+            module: String::new(),
+            source: None,
+            parameters: parameters
+                .iter()
+                .map(|(name, param_type)| crate::manifest::ParameterDef {
+                    name: name.to_string(),
+                    param_type: *param_type,
+                    required: true,
+                })
+                .collect(),
+            setup_template: String::new(),
+            methods: HashMap::new(),
+        };
+        (name.to_string(), manifest)
+    }
+
+    fn manifests() -> HashMap<String, ComponentManifest> {
+        HashMap::from([
+            manifest("LED", &[("gpio", ParameterType::GpioRef)]),
+            manifest("I2C", &[("i2c", ParameterType::I2cRef)]),
+            manifest(
+                "Mux",
+                &[
+                    ("i2c", ParameterType::I2cRef),
+                    ("upstream", ParameterType::I2cComponentRef),
+                ],
+            ),
+            manifest("Sensor", &[("bus", ParameterType::I2cComponentRef)]),
+        ])
+    }
+
+    fn order(yaml: &str) -> Result<Vec<InstanceId>> {
+        let config: EspforgeConfiguration = serde_yaml_ng::from_str(yaml).unwrap();
+        initialization_order(&config, &manifests())
+    }
+
+    fn component(name: &str) -> InstanceId {
+        InstanceId::Component(name.to_string())
+    }
+
+    fn device(name: &str) -> InstanceId {
+        InstanceId::Device(name.to_string())
+    }
+
+    #[test]
+    fn components_come_before_devices() {
+        let yaml = r#"
+            espforge: { name: order, platform: esp32c3 }
+            devices:
+              a_sensor: { using: Sensor, with: { bus: $z_bus } }
+            components:
+              z_bus: { using: I2C, with: { i2c: $i2c0 } }
+              z_led: { using: LED, with: { gpio: $led } }
+        "#;
+
+        assert_eq!(
+            order(yaml).unwrap(),
+            [component("z_bus"), component("z_led"), device("a_sensor")]
+        );
+    }
+
+    #[test]
+    fn independent_instances_keep_a_stable_order() {
+        let yaml = r#"
+            espforge: { name: order, platform: esp32c3 }
+            components:
+              c_led: { using: LED, with: { gpio: $c } }
+              a_sensor: { using: Sensor, with: { bus: $z_bus } }
+              b_led: { using: LED, with: { gpio: $b } }
+              z_bus: { using: I2C, with: { i2c: $i2c0 } }
+        "#;
+
+        // By name, except that a component waits for the bus it references
+        let expected = [
+            component("b_led"),
+            component("c_led"),
+            component("z_bus"),
+            component("a_sensor"),
+        ];
+        for _ in 0..3 {
+            assert_eq!(order(yaml).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn cycle_names_its_config_path() {
+        let yaml = r#"
+            espforge: { name: order, platform: esp32c3 }
+            components:
+              mux_b: { using: Mux, with: { i2c: $i2c0, upstream: $mux_a } }
+              mux_a: { using: Mux, with: { i2c: $i2c1, upstream: $mux_b } }
+        "#;
+
+        let error = order(yaml).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ConfigPath>().map(|p| p.0.as_str()),
+            Some("components.mux_a")
+        );
+        assert_eq!(
+            error.root_cause().to_string(),
+            "Reference cycle between components.mux_a, components.mux_b"
+        );
+    }
+
+    #[test]
+    fn dangling_reference_is_an_error() {
+        let yaml = r#"
+            espforge: { name: order, platform: esp32c3 }
+            devices:
+              sensor: { using: Sensor, with: { bus: $missing } }
+        "#;
+
+        let error = order(yaml).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ConfigPath>().map(|p| p.0.as_str()),
+            Some("devices.sensor.with.bus")
+        );
+        assert!(error.root_cause().to_string().contains("undefined component '$missing'"));
+    }
+}
@@ -1,20 +1,36 @@
 use crate::config::EspforgeConfiguration;
 use crate::manifest::{ComponentManifest, ParameterDef, ParameterType};
 use crate::resolver::actions::ActionResolver;
+use crate::resolver::dependencies::InstanceId;
 use crate::resolver::strategies::{ParameterStrategy, ResolutionContext, StrategyRegistration};
 use anyhow::{Context, Result, anyhow};
 use inventory;
 use serde::Serialize;
 use serde_yaml_ng::Value;
 use std::collections::HashMap;
+use std::fmt;
 use tera::Tera;
 
 pub mod actions;
+pub mod dependencies;
 pub mod ruchy_bridge;
 pub mod strategies;
 
 type ActionList = Vec<HashMap<String, Value>>;
 
+/// Error context naming the YAML node a resolver error comes from.
+///
+/// Recover it with `error.downcast_ref::<ConfigPath>()` to point diagnostics
+/// at the offending line.
+#[derive(Debug, Clone)]
+pub struct ConfigPath(pub String);
+
+impl fmt::Display for ConfigPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at {}", self.0)
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct RenderContext {
     pub includes: Vec<String>,
@@ -65,9 +81,11 @@ impl ContextResolver {
         config: &EspforgeConfiguration,
         manifests: &HashMap<String, ComponentManifest>,
     ) -> Result<RenderContext> {
+        let order = dependencies::initialization_order(config, manifests)?;
+
         let mut includes = Vec::new();
-        let components_map = self.resolve_components(config, manifests, &mut includes)?;
-        let devices_map = self.resolve_devices(config, manifests, &mut includes)?;
+        let mut components_map = self.resolve_components(config, manifests, &mut includes)?;
+        let mut devices_map = self.resolve_devices(config, manifests, &mut includes)?;
 
         let variables_code = self.resolve_variables(config)?;
 
@@ -79,9 +97,14 @@ impl ContextResolver {
         let task_definitions = Vec::new();
         let task_spawns = Vec::new();
 
-        // Append initializations in order (components then devices)
-        let mut initializations: Vec<String> = components_map.values().cloned().collect();
-        initializations.extend(devices_map.values().cloned());
+        // Every instance comes after the components it references
+        let initializations: Vec<String> = order
+            .iter()
+            .filter_map(|id| match id {
+                InstanceId::Component(name) => components_map.remove(name),
+                InstanceId::Device(name) => devices_map.remove(name),
+            })
+            .collect();
 
         Ok(RenderContext {
             includes,
@@ -97,7 +120,10 @@ impl ContextResolver {
     fn resolve_variables(&self, config: &EspforgeConfiguration) -> Result<Vec<String>> {
         let mut vars = Vec::new();
         if let Some(app) = &config.app {
-            for (name, var_config) in &app.variables {
+            let mut variables: Vec<_> = app.variables.iter().collect();
+            variables.sort_by_key(|(name, _)| *name);
+
+            for (name, var_config) in variables {
                 // Determine Rust type
                 let rust_type = match var_config.type_name.as_str() {
                     "bool" => "bool",
@@ -150,6 +176,9 @@ impl ContextResolver {
 
         let mut rendered_inits = HashMap::new();
 
+        let mut components: Vec<_> = components.iter().collect();
+        components.sort_by_key(|(name, _)| *name);
+
         for (name, instance) in components {
             let rendered = self.resolve_single_component(
                 name,
@@ -172,15 +201,22 @@ impl ContextResolver {
         resolution_ctx: &ResolutionContext,
         includes: &mut Vec<String>,
     ) -> Result<String> {
-        let manifest = self.get_manifest(manifests, &instance.using)?;
+        let path = format!("components.{}", name);
+        let manifest = self
+            .get_manifest(manifests, &instance.using)
+            .context(ConfigPath(format!("{}.using", path)))?;
 
         let params_context = self
-            .resolve_parameters(manifest, &instance.with, resolution_ctx)
+            .resolve_parameters(manifest, &instance.with, resolution_ctx, &path)
             .with_context(|| format!("Failed to resolve parameters for component '{}'", name))?;
 
         let init_code = self.render_component_template(name, &params_context, manifest)?;
 
-        includes.extend_from_slice(&manifest.requires);
+        for include in &manifest.requires {
+            if !includes.contains(include) {
+                includes.push(include.clone());
+            }
+        }
 
         Ok(init_code)
     }
@@ -202,6 +238,9 @@ impl ContextResolver {
 
         let mut rendered_inits = HashMap::new();
 
+        let mut devices: Vec<_> = devices.iter().collect();
+        devices.sort_by_key(|(name, _)| *name);
+
         for (name, instance) in devices {
             let rendered =
                 self.resolve_single_device(name, instance, manifests, &resolution_ctx, includes)?;
@@ -219,15 +258,22 @@ impl ContextResolver {
         resolution_ctx: &ResolutionContext,
         includes: &mut Vec<String>,
     ) -> Result<String> {
-        let manifest = self.get_manifest(manifests, &instance.using)?;
+        let path = format!("devices.{}", name);
+        let manifest = self
+            .get_manifest(manifests, &instance.using)
+            .context(ConfigPath(format!("{}.using", path)))?;
 
         let params_context = self
-            .resolve_parameters(manifest, &instance.with, resolution_ctx)
+            .resolve_parameters(manifest, &instance.with, resolution_ctx, &path)
             .with_context(|| format!("Failed to resolve parameters for device '{}'", name))?;
 
         let init_code = self.render_component_template(name, &params_context, manifest)?;
 
-        includes.extend_from_slice(&manifest.requires);
+        for include in &manifest.requires {
+            if !includes.contains(include) {
+                includes.push(include.clone());
+            }
+        }
 
         Ok(init_code)
     }
@@ -262,16 +308,21 @@ impl ContextResolver {
         manifest: &ComponentManifest,
         user_params: &HashMap<String, Value>,
         ctx: &ResolutionContext,
+        instance_path: &str,
     ) -> Result<HashMap<String, Value>> {
         let mut resolved_params = HashMap::new();
 
         for param_def in &manifest.parameters {
             let value = user_params.get(&param_def.name);
+            let path = format!("{}.with.{}", instance_path, param_def.name);
 
-            self.validate_required_parameter(param_def.required, value, &param_def.name)?;
+            self.validate_required_parameter(param_def.required, value, &param_def.name)
+                .context(ConfigPath(path.clone()))?;
 
             if let Some(val) = value {
-                let resolved = self.resolve_single_parameter(param_def, val, ctx)?;
+                let resolved = self
+                    .resolve_single_parameter(param_def, val, ctx)
+                    .context(ConfigPath(path))?;
                 resolved_params.insert(param_def.name.clone(), resolved);
             }
         }
@@ -321,12 +372,15 @@ impl ContextResolver {
             .iter()
             .enumerate()
             .map(|(index, action)| {
-                let (key, value) = action.iter().next().ok_or_else(|| {
-                    anyhow!("Empty action in {} block at index {}", block_name, index)
-                })?;
+                let (key, value) = action
+                    .iter()
+                    .next()
+                    .ok_or_else(|| anyhow!("Empty action in {} block at index {}", block_name, index))
+                    .context(ConfigPath(format!("app.{}[{}]", block_name, index)))?;
 
                 self.action_resolver
                     .resolve(key, value, config, manifests, &mut self.tera)
+                    .context(ConfigPath(format!("app.{}[{}].{}", block_name, index, key)))
             })
             .collect()
     }
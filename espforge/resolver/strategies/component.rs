@@ -5,7 +5,10 @@ use anyhow::{Result, anyhow};
 use espforge_macros::auto_register_param_strategy;
 use serde_yaml_ng::Value;
 
-/// Strategy for resolving I2C Component references.
+/// Strategy for resolving I2C and SPI bus Component references.
+///
+/// Bus components stay owned by `main`; device templates borrow them
+/// (`&{{ params.spi }}`), so several devices can share one bus.
 #[derive(Default)]
 #[auto_register_param_strategy(ParameterType::I2cComponentRef, ParameterType::SpiComponentRef)]
 pub struct ComponentRefStrategy;
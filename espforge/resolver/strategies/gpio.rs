@@ -1,6 +1,7 @@
 use crate::register_strategy;
 use crate::resolver::strategies::{ParameterStrategy, ResolutionContext};
 use crate::resolver::strategies::utils::resolve_hardware_resource;
+use crate::resolver::strategies::views::GpioTemplateView;
 use crate::manifest::ParameterType;
 use anyhow::Result;
 use espforge_macros::auto_register_param_strategy;
@@ -16,7 +17,7 @@ impl ParameterStrategy for GpioStrategy {
             value,
             ctx,
             |hw| &hw.gpio,
-            |c| c.clone()
+            |c| GpioTemplateView::from(c),
         )
     }
 }
@@ -9,6 +9,7 @@ pub mod utils;
 pub mod views;
 pub mod gpio;
 pub mod i2c;
+pub mod primitive;
 pub mod spi;
 pub mod uart;
 
@@ -0,0 +1,48 @@
+use crate::manifest::ParameterType;
+use crate::register_strategy;
+use crate::resolver::strategies::{ParameterStrategy, ResolutionContext};
+use anyhow::{Result, anyhow};
+use espforge_macros::auto_register_param_strategy;
+use serde_yaml_ng::Value;
+
+/// Plain string parameters, passed to the template as-is.
+#[derive(Default)]
+#[auto_register_param_strategy(ParameterType::String)]
+pub struct StringStrategy;
+
+impl ParameterStrategy for StringStrategy {
+    fn resolve(&self, value: &Value, _ctx: &ResolutionContext) -> Result<Value> {
+        match value {
+            Value::String(_) => Ok(value.clone()),
+            other => Err(anyhow!("Expected a string, got: {:?}", other)),
+        }
+    }
+}
+
+/// Integer parameters, e.g. an I2C address or a baud rate.
+#[derive(Default)]
+#[auto_register_param_strategy(ParameterType::Integer)]
+pub struct IntegerStrategy;
+
+impl ParameterStrategy for IntegerStrategy {
+    fn resolve(&self, value: &Value, _ctx: &ResolutionContext) -> Result<Value> {
+        match value {
+            Value::Number(n) if n.is_i64() || n.is_u64() => Ok(value.clone()),
+            other => Err(anyhow!("Expected an integer, got: {:?}", other)),
+        }
+    }
+}
+
+/// Boolean parameters.
+#[derive(Default)]
+#[auto_register_param_strategy(ParameterType::Boolean)]
+pub struct BooleanStrategy;
+
+impl ParameterStrategy for BooleanStrategy {
+    fn resolve(&self, value: &Value, _ctx: &ResolutionContext) -> Result<Value> {
+        match value {
+            Value::Bool(_) => Ok(value.clone()),
+            other => Err(anyhow!("Expected a boolean, got: {:?}", other)),
+        }
+    }
+}
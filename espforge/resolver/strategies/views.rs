@@ -1,4 +1,4 @@
-use crate::config::SpiConfig;
+use crate::config::{GpioPinConfig, PinDirection, PinLevel, SpiConfig};
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -26,3 +26,58 @@ impl From<&SpiConfig> for SpiTemplateView {
     }
 }
 
+#[derive(Serialize)]
+pub struct GpioTemplateView {
+    pub pin: u8,
+    pub direction: PinDirection,
+    pub pullup: bool,
+    pub pulldown: bool,
+    pub open_drain: bool,
+    /// In mA; 0 keeps esp-hal's default
+    pub drive_strength: u8,
+    pub initial_high: bool,
+    pub inverted: bool,
+    /// A `PinSettings { .. }` expression for the `with_settings` constructors
+    /// of `platform/gpio`; empty when the pin needs no more than its pulls.
+    pub settings: String,
+}
+
+impl From<&GpioPinConfig> for GpioTemplateView {
+    fn from(config: &GpioPinConfig) -> Self {
+        let drive_strength = config.drive_strength.unwrap_or(0);
+        let initial_high = config.initial == PinLevel::High;
+        let input_output = config.direction == PinDirection::InputOutput;
+
+        let plain = !config.open_drain
+            && drive_strength == 0
+            && !initial_high
+            && !config.inverted
+            && !input_output;
+        let settings = if plain {
+            String::new()
+        } else {
+            format!(
+                "PinSettings {{ pull_up: {}, pull_down: {}, open_drain: {}, drive_strength: {}, initial_high: {}, inverted: {}, input_output: {} }}",
+                config.pullup,
+                config.pulldown,
+                config.open_drain,
+                drive_strength,
+                initial_high,
+                config.inverted,
+                input_output
+            )
+        };
+
+        Self {
+            pin: config.pin,
+            direction: config.direction,
+            pullup: config.pullup,
+            pulldown: config.pulldown,
+            open_drain: config.open_drain,
+            drive_strength,
+            initial_high,
+            inverted: config.inverted,
+            settings,
+        }
+    }
+}
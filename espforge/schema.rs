@@ -0,0 +1,235 @@
+use crate::board::{board_names, load_board};
+use crate::config::EspforgeConfiguration;
+use crate::config::include::PACKAGES_KEY;
+use crate::config::profiles::PROFILES_KEY;
+use crate::generate::{ManifestKind, load_manifests_of};
+use crate::manifest::{ComponentManifest, ParameterDef, ParameterType};
+use anyhow::{Context, Result};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeSet, HashMap};
+
+/// `$name` references to `esp32:` resources or other components.
+const REFERENCE_PATTERN: &str = "^\\$[A-Za-z_][A-Za-z0-9_]*$";
+
+/// A scalar that is a single `${name}` substitution.
+const SUBSTITUTION_PATTERN: &str = "^\\$\\{[^}]+\\}$";
+
+/// Builds a JSON Schema (draft 2020-12) for the espforge YAML format.
+///
+/// The structure comes from the config types; `components.*.with` and
+/// `devices.*.with` are narrowed per `using:` value from the embedded manifests.
+pub fn generate_schema() -> Result<Value> {
+    let mut schema = schemars::schema_for!(EspforgeConfiguration).to_value();
+
+    let defs = schema
+        .get_mut("$defs")
+        .and_then(Value::as_object_mut)
+        .context("Generated schema has no $defs")?;
+
+    for (def, kind) in [
+        ("ComponentConfig", ManifestKind::Component),
+        ("DeviceConfig", ManifestKind::Device),
+    ] {
+        let manifests = load_manifests_of(kind)?;
+        let entry = defs
+            .get_mut(def)
+            .with_context(|| format!("Generated schema has no definition for {}", def))?;
+        apply_manifests(entry, &manifests);
+    }
+
+    // `espforge.platform` can come from the board, which must be a bundled one
+    if let Some(project) = defs.get_mut("EspforgeConfig") {
+        if let Some(required) = project.get_mut("required").and_then(Value::as_array_mut) {
+            required.retain(|field| field != "platform");
+        }
+        if let Some(board) = project.pointer_mut("/properties/board") {
+            board["enum"] = json!(board_names());
+        }
+    }
+
+    // Pins may be labels of the board, which also fills in left-out bus pins
+    let labels = board_labels()?;
+    for (def, fields) in [
+        ("GpioPinConfig", &["pin"][..]),
+        ("I2cConfig", &["sda", "scl"]),
+        ("SpiConfig", &["sck", "mosi", "miso", "cs"]),
+        ("UartConfig", &["tx", "rx"]),
+    ] {
+        let Some(definition) = defs.get_mut(def) else {
+            continue;
+        };
+        for field in fields {
+            if let Some(property) = definition.pointer_mut(&format!("/properties/{}", field)) {
+                *property = number_or_label(property.take(), &labels);
+            }
+        }
+        if def != "GpioPinConfig"
+            && let Some(required) = definition.get_mut("required").and_then(Value::as_array_mut)
+        {
+            required.retain(|field| !fields.iter().any(|f| field == f));
+        }
+    }
+
+    // Resolved before the configuration is parsed, so not part of its type
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        // Each profile is a partial configuration overlaid on the file
+        properties.insert(
+            PROFILES_KEY.to_string(),
+            json!({
+                "description": "Named overlays selected with `espforge compile --profile <name>`",
+                "type": "object",
+                "additionalProperties": { "type": "object" }
+            }),
+        );
+        properties.insert(
+            PACKAGES_KEY.to_string(),
+            json!({
+                "description": "Fragments merged into the configuration, in order, before its own content",
+                "type": "array",
+                "items": {
+                    "anyOf": [
+                        { "type": "string", "description": "Path of a YAML file, relative to this one" },
+                        { "type": "object" }
+                    ]
+                }
+            }),
+        );
+    }
+
+    allow_substitutions(&mut schema);
+
+    Ok(schema)
+}
+
+/// Every pin label of the bundled boards, sorted.
+fn board_labels() -> Result<Vec<String>> {
+    let mut labels = BTreeSet::new();
+    for name in board_names() {
+        labels.extend(load_board(&name)?.pins.into_keys());
+    }
+    Ok(labels.into_iter().collect())
+}
+
+/// Widens a pin number property to also accept a board label.
+fn number_or_label(number: Value, labels: &[String]) -> Value {
+    any_of(
+        number,
+        json!({ "type": "string", "enum": labels, "description": "A pin label of espforge.board" }),
+    )
+}
+
+/// Lets every integer and boolean in `schema` be a `${name}` reference
+/// instead, which is expanded before the configuration is parsed.
+fn allow_substitutions(schema: &mut Value) {
+    let substitution = || json!({ "type": "string", "pattern": SUBSTITUTION_PATTERN });
+    match schema {
+        Value::Object(object) if is_scalar(object) => {
+            *schema = any_of(schema.take(), substitution());
+        }
+        Value::Object(object) => {
+            if let Some(Value::Array(options)) = object.get_mut("anyOf")
+                && options.iter().any(|o| o.as_object().is_some_and(is_scalar))
+            {
+                options.push(substitution());
+                return;
+            }
+            object.values_mut().for_each(allow_substitutions);
+        }
+        Value::Array(items) => items.iter_mut().for_each(allow_substitutions),
+        _ => {}
+    }
+}
+
+/// Whether a schema object describes an integer or boolean.
+fn is_scalar(object: &Map<String, Value>) -> bool {
+    let scalar = |t: &Value| matches!(t.as_str(), Some("integer" | "boolean"));
+    match object.get("type") {
+        Some(Value::Array(types)) => types.iter().any(scalar),
+        Some(t) => scalar(t),
+        None => false,
+    }
+}
+
+/// `anyOf` the two schemas, keeping the first one's description and default
+/// on the outside.
+fn any_of(mut first: Value, second: Value) -> Value {
+    let mut property = Map::new();
+    if let Some(first) = first.as_object_mut() {
+        for key in ["description", "default"] {
+            if let Some(value) = first.remove(key) {
+                property.insert(key.to_string(), value);
+            }
+        }
+    }
+    property.insert("anyOf".to_string(), json!([first, second]));
+    Value::Object(property)
+}
+
+/// Restricts `using:` to the known manifests and adds one `if`/`then` per
+/// manifest describing its `with:` parameters.
+fn apply_manifests(definition: &mut Value, manifests: &HashMap<String, ComponentManifest>) {
+    let mut names: Vec<&String> = manifests.keys().collect();
+    names.sort();
+
+    let rules: Vec<Value> = names
+        .iter()
+        .map(|name| {
+            let manifest = &manifests[*name];
+            json!({
+                "if": {
+                    "properties": { "using": { "const": name } },
+                    "required": ["using"]
+                },
+                "then": {
+                    "properties": { "with": with_schema(&manifest.parameters) }
+                }
+            })
+        })
+        .collect();
+
+    if let Some(using) = definition.pointer_mut("/properties/using") {
+        using["enum"] = json!(names);
+    }
+    definition["allOf"] = Value::Array(rules);
+}
+
+fn with_schema(parameters: &[ParameterDef]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    for param in parameters {
+        properties.insert(param.name.clone(), parameter_schema(param.param_type));
+        if param.required {
+            required.push(param.name.clone());
+        }
+    }
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false
+    })
+}
+
+fn parameter_schema(param_type: ParameterType) -> Value {
+    match param_type {
+        ParameterType::String => json!({ "type": "string" }),
+        ParameterType::Integer => json!({ "type": "integer" }),
+        ParameterType::Boolean => json!({ "type": "boolean" }),
+        ParameterType::GpioRef => reference("Reference to an esp32.gpio entry, e.g. $led"),
+        ParameterType::I2cRef => reference("Reference to an esp32.i2c entry"),
+        ParameterType::SpiRef => reference("Reference to an esp32.spi entry"),
+        ParameterType::UartRef => reference("Reference to an esp32.uart entry"),
+        ParameterType::I2cComponentRef => reference("Reference to an I2C bus component"),
+        ParameterType::SpiComponentRef => reference("Reference to an SPI bus component"),
+    }
+}
+
+fn reference(description: &str) -> Value {
+    json!({
+        "type": "string",
+        "pattern": REFERENCE_PATTERN,
+        "description": description
+    })
+}
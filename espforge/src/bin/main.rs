@@ -1,7 +1,12 @@
 use anyhow::{Context, Error, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use espforge_lib::compile;
+use espforge_lib::config::{GeneratorBackend, PlatformConfig};
 use espforge_lib::export;
+use espforge_lib::inspect;
+use espforge_lib::menuconfig;
+use espforge_lib::pack;
+use espforge_lib::schema;
 use espforge_lib::template_utils::list_examples_by_category;
 use std::{fs::metadata, path::PathBuf};
 // Import dialoguer traits
@@ -18,8 +23,27 @@ struct Cli {
 enum Commands {
     Compile {
         file: PathBuf,
+        /// Override the platform/chip declared in the configuration (e.g. esp32s3)
         #[arg(long, short = 'c')]
         chip: Option<String>,
+        /// Project skeleton generator: esp-generate or builtin (overrides espforge.generator.backend)
+        #[arg(long, short = 'b')]
+        backend: Option<GeneratorBackend>,
+        /// Overwrite generated files that have local changes outside user regions
+        #[arg(long)]
+        force: bool,
+        /// Print a diff of what would change instead of writing the project
+        #[arg(long)]
+        dry_run: bool,
+        /// Generate the project into this directory (overrides espforge.output)
+        #[arg(long, short = 'o')]
+        out_dir: Option<PathBuf>,
+        /// Recompile whenever the configuration, app.ruchy or local templates change
+        #[arg(long, short = 'w')]
+        watch: bool,
+        /// Overlay this entry of the configuration's `profiles:` (e.g. dev, prod)
+        #[arg(long, short = 'p')]
+        profile: Option<String>,
     },
     Examples {
         /// The name of the example template to use (e.g. blink, uart).
@@ -34,18 +58,201 @@ enum Commands {
         #[arg(long, short = 'c')]
         chip: Option<String>,
     },
+    /// Validate a configuration without generating a project
+    Check {
+        file: PathBuf,
+        /// Override the platform/chip declared in the configuration (e.g. esp32s3)
+        #[arg(long, short = 'c')]
+        chip: Option<String>,
+        /// Overlay this entry of the configuration's `profiles:` (e.g. dev, prod)
+        #[arg(long, short = 'p')]
+        profile: Option<String>,
+        #[arg(long, short = 'f', value_enum, default_value_t = CheckFormat::Text)]
+        format: CheckFormat,
+    },
+    /// List the components, devices, globals and methods available to a configuration
+    Inspect {
+        file: PathBuf,
+        #[arg(long, short = 'f', value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Interactively create or edit a configuration file
+    Menuconfig { file: PathBuf },
+    /// Install and list packs of shared components, devices and examples
+    Pack {
+        #[command(subcommand)]
+        command: PackCommands,
+    },
+    /// Print a JSON Schema for the configuration format (for yaml-language-server)
+    Schema {
+        /// Write the schema to this file instead of stdout
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PackCommands {
+    /// Install a pack from a directory or a .tar, .tar.gz or .tgz archive
+    Add { source: PathBuf },
+    /// List the installed packs
+    List,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CheckFormat {
+    Text,
+    Json,
+    Sarif,
 }
 
 pub fn main() -> Result<(), Error> {
     let cli = Cli::parse();
     match cli.command {
-        Commands::Compile { file, chip: _ } => {
+        Commands::Compile {
+            file,
+            chip,
+            backend,
+            force,
+            dry_run,
+            out_dir,
+            watch,
+            profile,
+        } => {
             metadata(&file)
                 .with_context(|| format!("Configuration file {} not found", &file.display()))?;
             if !file.is_file() {
                 anyhow::bail!("Path {} is not a file", file.display());
             }
-            compile::compile(&file)?;
+            let options = compile::CompileOptions {
+                override_platform: chip,
+                backend,
+                force,
+                dry_run,
+                out_dir,
+                profile,
+            };
+            if watch {
+                return compile::watch::watch(&file, options);
+            }
+            let report = compile::compile(&file, options)?;
+            print!("{}", report);
+            if report.has_conflicts() && !dry_run {
+                anyhow::bail!(
+                    "Some files have local changes and were not updated. Move the changes into a user region or a separate module, or re-run with --force to overwrite them."
+                );
+            }
+            Ok(())
+        }
+
+        Commands::Check {
+            file,
+            chip,
+            profile,
+            format,
+        } => {
+            let options = compile::CompileOptions {
+                override_platform: chip,
+                profile,
+                ..Default::default()
+            };
+            let report = compile::check::check(&file, &options);
+            match format {
+                CheckFormat::Text => print!("{}", report),
+                CheckFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                CheckFormat::Sarif => {
+                    println!("{}", serde_json::to_string_pretty(&report.to_sarif())?)
+                }
+            }
+            if report.has_errors() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+
+        Commands::Inspect { file, format } => {
+            let report = inspect::inspect(&file)?;
+            match format {
+                OutputFormat::Text => print!("{}", report),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            }
+            Ok(())
+        }
+
+        Commands::Menuconfig { file } => {
+            if file.exists() && !file.is_file() {
+                anyhow::bail!("Path {} is not a file", file.display());
+            }
+            menuconfig::run(&file)
+        }
+
+        Commands::Pack { command } => match command {
+            PackCommands::Add { source } => {
+                let outcome = pack::install(&source)?;
+                let metadata = &outcome.pack.metadata;
+                match outcome.replaced {
+                    Some(old) => println!(
+                        "Updated pack '{}' {} -> {} in {}",
+                        metadata.name,
+                        old,
+                        metadata.version,
+                        outcome.pack.root.display()
+                    ),
+                    None => println!(
+                        "Installed pack '{}' {} into {}",
+                        metadata.name,
+                        metadata.version,
+                        outcome.pack.root.display()
+                    ),
+                }
+                Ok(())
+            }
+            PackCommands::List => {
+                let packs = pack::installed_packs()?;
+                if packs.is_empty() {
+                    match pack::packs_dir() {
+                        Some(dir) => println!("No packs installed in {}", dir.display()),
+                        None => println!("No packs installed"),
+                    }
+                }
+                for installed in packs {
+                    let metadata = &installed.metadata;
+                    let chips = if metadata.chips.is_empty() {
+                        "all chips".to_string()
+                    } else {
+                        metadata
+                            .chips
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    print!("{} {} ({})", metadata.name, metadata.version, chips);
+                    if !metadata.description.is_empty() {
+                        print!(": {}", metadata.description);
+                    }
+                    if !installed.is_compatible() {
+                        print!(" [requires espforge {}, not loaded]", metadata.espforge);
+                    }
+                    println!();
+                }
+                Ok(())
+            }
+        },
+
+        Commands::Schema { output } => {
+            let schema = serde_json::to_string_pretty(&schema::generate_schema()?)?;
+            match output {
+                Some(path) => std::fs::write(&path, schema)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => println!("{}", schema),
+            }
             Ok(())
         }
 
@@ -104,13 +311,19 @@ pub fn main() -> Result<(), Error> {
 
             // 3. Interactive Chip Selection (if not provided)
             if chip.is_none() {
-                let chips = vec![
-                    "esp32c3", "esp32c6", "esp32s3", "esp32s2", "esp32", "esp32h2",
-                ];
+                // Only offer chips that have every pin and bus the example uses
+                let chips = export::supported_platforms(&name)?;
+                if chips.is_empty() {
+                    anyhow::bail!("Example '{}' does not fit any supported chip.", name);
+                }
+                let default = chips
+                    .iter()
+                    .position(|c| *c == PlatformConfig::ESP32C3)
+                    .unwrap_or(0);
 
                 let selection = Select::with_theme(&theme)
                     .with_prompt("Select Target Chip")
-                    .default(0) // Default to C3
+                    .default(default)
                     .items(&chips)
                     .interact()?;
 
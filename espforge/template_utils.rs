@@ -1,3 +1,5 @@
+use crate::generate::output::ProjectOutput;
+use crate::pack;
 use anyhow::Result;
 use include_dir::{Dir, include_dir};
 use std::collections::HashMap;
@@ -27,7 +29,8 @@ pub fn find_template_path(name: &str) -> Option<String> {
     None
 }
 
-/// Helper to list examples for the CLI
+/// Helper to list examples for the CLI, the embedded ones merged with those
+/// of the installed packs
 pub fn list_examples_by_category() -> HashMap<String, Vec<String>> {
     let mut map = HashMap::new();
     for entry in TEMPLATES_DIR.dirs() {
@@ -56,13 +59,32 @@ pub fn list_examples_by_category() -> HashMap<String, Vec<String>> {
             map.insert(category, examples);
         }
     }
+
+    let packs = pack::active_packs().unwrap_or_else(|e| {
+        eprintln!("Warning: failed to load packs: {:#}", e);
+        Vec::new()
+    });
+    for pack in packs {
+        let Ok(categories) = pack.examples() else {
+            continue;
+        };
+        for (category, examples) in categories {
+            let list: &mut Vec<String> = map.entry(category).or_default();
+            for example in examples {
+                if !list.contains(&example) {
+                    list.push(example);
+                }
+            }
+            list.sort();
+        }
+    }
     map
 }
 
 /// Processes a template directory: renders .tera files and copies others
 pub fn process_template_directory(
     template_path: &str,
-    project_name: &str,
+    output: &mut ProjectOutput,
     context: &tera::Context,
 ) -> Result<()> {
     let root = get_templates();
@@ -75,7 +97,7 @@ pub fn process_template_directory(
             let path = file.path();
             // Get path relative to the specific template folder
             let relative_path = path.strip_prefix(template_path).unwrap_or(path);
-            let dest_path = Path::new(project_name).join(relative_path);
+            let dest_path = relative_path.to_path_buf();
             let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
             // SKIP Cargo.toml.tera to prevent overwriting the generated Cargo.toml
@@ -84,10 +106,6 @@ pub fn process_template_directory(
                 continue;
             }
 
-            if let Some(parent) = dest_path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-
             if name.ends_with(".tera") {
                 let content = file
                     .contents_utf8()
@@ -97,11 +115,10 @@ pub fn process_template_directory(
                     .map_err(|e| anyhow::anyhow!("Template error in {}: {}", name, e))?;
 
                 // Save without .tera extension
-                let dest_clean = dest_path.with_extension("");
-                fs::write(dest_clean, rendered)?;
+                output.add(dest_path.with_extension(""), rendered);
             } else {
                 // Copy raw file
-                fs::write(&dest_path, file.contents())?;
+                output.add(dest_path, file.contents());
             }
         }
     }
@@ -4,6 +4,9 @@ pub mod components;
 pub mod globals;
 pub mod platform;
 pub mod devices; 
+// Your own modules (e.g. `pub mod app;` for src/app.rs) are kept across compiles
+// espforge:user-begin modules
+// espforge:user-end modules
 
 pub mod prelude {
     pub use crate::components::*;
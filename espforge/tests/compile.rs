@@ -0,0 +1,650 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+const BLINK: &str = r#"
+espforge:
+  name: blink
+  platform: esp32c3
+
+esp32:
+  gpio:
+    gpio2: { pin: 18, direction: output }
+
+components:
+  red_led:
+    using: LED
+    with:
+      gpio: $gpio2
+
+app:
+  loop:
+    - $red_led.toggle:
+    - delay.delay_millis: 500
+"#;
+
+/// `espforge` run in `dir`, with a data directory of its own so that packs
+/// installed on this machine cannot affect the test.
+fn espforge(dir: &assert_fs::TempDir) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("espforge");
+    cmd.current_dir(dir.path())
+        .env("ESPFORGE_DATA_DIR", dir.path().join("data"));
+    cmd
+}
+
+#[test]
+fn compile_with_builtin_backend() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("blink.yaml").write_str(BLINK).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "--backend", "builtin"])
+        .assert()
+        .success();
+
+    let project = dir.child("blink");
+    project.child("Cargo.toml").assert(predicate::str::contains(
+        r#"features = ["esp32c3", "log-04", "unstable"]"#,
+    ));
+    project
+        .child(".cargo/config.toml")
+        .assert(predicate::str::contains(
+            r#"target = "riscv32imc-unknown-none-elf""#,
+        ));
+    project
+        .child("rust-toolchain.toml")
+        .assert(predicate::str::contains(r#"channel = "stable""#));
+    project
+        .child("build.rs")
+        .assert(predicate::str::contains("-Tlinkall.x"));
+    project
+        .child("src/bin/main.rs")
+        .assert(predicate::str::contains("let mut red_led = LED::new(18);"));
+
+    // Only the modules blink uses are copied
+    project
+        .child("src/components/led.rs")
+        .assert(predicate::path::exists());
+    project
+        .child("src/platform/gpio.rs")
+        .assert(predicate::path::exists());
+    project
+        .child("src/components/i2c.rs")
+        .assert(predicate::path::missing());
+    project
+        .child("src/devices/ssd1306.rs")
+        .assert(predicate::path::missing());
+    project
+        .child("Cargo.toml")
+        .assert(predicate::str::contains("embedded-hal").not());
+}
+
+#[test]
+fn compile_with_i2c_bus_adds_embedded_hal() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = "espforge:\n  name: bus\n  platform: esp32c3\nesp32:\n  i2c:\n    i2c0: { i2c: 0, sda: 6, scl: 7 }\ncomponents:\n  bus:\n    using: i2c\n    with:\n      i2c: $i2c0\n";
+    dir.child("bus.yaml").write_str(config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "bus.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("bus/Cargo.toml").assert(
+        predicate::str::contains(r#"embedded-hal = "1.0.0""#)
+            .and(predicate::str::contains(r#"embedded-hal-bus = "0.3""#)),
+    );
+}
+
+#[test]
+fn compile_with_builtin_backend_for_xtensa_async() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = BLINK
+        .replace(
+            "platform: esp32c3",
+            "platform: esp32s3\n  enable_async: true",
+        )
+        .replace("pin: 18", "pin: 4");
+    dir.child("blink.yaml").write_str(&config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+
+    let project = dir.child("blink");
+    project
+        .child("Cargo.toml")
+        .assert(predicate::str::contains("esp-rtos"));
+    project
+        .child(".cargo/config.toml")
+        .assert(predicate::str::contains("build-std"));
+    project
+        .child("rust-toolchain.toml")
+        .assert(predicate::str::contains(r#"channel = "esp""#));
+}
+
+#[test]
+fn compile_with_defmt_generator_option() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = BLINK.replace(
+        "platform: esp32c3",
+        "platform: esp32c3\n  generator:\n    backend: builtin\n    options: [defmt, unstable-hal, esp-backtrace]",
+    );
+    dir.child("blink.yaml").write_str(&config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml"])
+        .assert()
+        .success();
+
+    let project = dir.child("blink");
+    project.child("Cargo.toml").assert(
+        predicate::str::contains(r#"default = ["esp32c3", "log-defmt"]"#)
+            .and(predicate::str::contains("log =").not()),
+    );
+    project
+        .child("build.rs")
+        .assert(predicate::str::contains("-Tdefmt.x"));
+    project
+        .child(".cargo/config.toml")
+        .assert(predicate::str::contains("--log-format defmt"));
+}
+
+#[test]
+fn recompile_keeps_user_code() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("blink.yaml").write_str(BLINK).unwrap();
+    let compile = || {
+        let mut cmd = espforge(&dir);
+        cmd.args(["compile", "blink.yaml", "-b", "builtin"]);
+        cmd
+    };
+
+    compile().assert().success();
+    compile()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("16 unchanged, 0 conflict(s)"));
+
+    // Code inside a user region survives a configuration change
+    let main_rs = dir.child("blink/src/bin/main.rs");
+    let edited = std::fs::read_to_string(main_rs.path()).unwrap().replace(
+        "    // espforge:user-end setup",
+        "    let mut count = 0;\n    // espforge:user-end setup",
+    );
+    main_rs.write_str(&edited).unwrap();
+    dir.child("blink.yaml")
+        .write_str(&BLINK.replace("pin: 18", "pin: 5"))
+        .unwrap();
+
+    compile()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("updated   src/bin/main.rs"));
+    main_rs.assert(
+        predicate::str::contains("let mut count = 0;").and(predicate::str::contains("LED::new(5)")),
+    );
+
+    // Edits outside user regions are kept and reported
+    let edited = std::fs::read_to_string(main_rs.path())
+        .unwrap()
+        .replace("#![no_main]", "#![no_main]\n// mine");
+    main_rs.write_str(&edited).unwrap();
+    dir.child("blink.yaml")
+        .write_str(&BLINK.replace("pin: 18", "pin: 6"))
+        .unwrap();
+
+    compile()
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("conflict  src/bin/main.rs"));
+    main_rs
+        .assert(predicate::str::contains("// mine").and(predicate::str::contains("LED::new(5)")));
+
+    compile().arg("--force").assert().success();
+    main_rs.assert(
+        predicate::str::contains("// mine")
+            .not()
+            .and(predicate::str::contains("LED::new(6)"))
+            .and(predicate::str::contains("let mut count = 0;")),
+    );
+}
+
+#[test]
+fn recompile_for_another_chip() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("blink.yaml").write_str(BLINK).unwrap();
+    let compile = |args: &[&str]| {
+        let mut cmd = espforge(&dir);
+        cmd.args(["compile", "blink.yaml", "-b", "builtin"]).args(args);
+        cmd
+    };
+
+    compile(&[]).assert().success();
+    dir.child("blink/Cargo.toml").assert(predicate::str::contains(
+        "[package.metadata.espforge]\nplatform = \"esp32c3\"",
+    ));
+
+    // The skeleton was made for another chip, so it is not silently kept
+    compile(&["--chip", "esp32s3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "was generated for esp32c3 [log, esp-backtrace, unstable-hal, wokwi], but the configuration now asks for esp32s3",
+        ));
+    dir.child("blink/Cargo.toml")
+        .assert(predicate::str::contains("esp32s3").not());
+
+    compile(&["--chip", "esp32s3", "--force"]).assert().success();
+    let project = dir.child("blink");
+    project.child("Cargo.toml").assert(
+        predicate::str::contains("esp32c3")
+            .not()
+            .and(predicate::str::contains(r#"default = ["esp32s3"]"#))
+            .and(predicate::str::contains(r#"features = ["esp32s3", "log-04", "unstable"]"#)),
+    );
+    project
+        .child(".cargo/config.toml")
+        .assert(predicate::str::contains(r#"target = "xtensa-esp32s3-none-elf""#));
+    project
+        .child("rust-toolchain.toml")
+        .assert(predicate::str::contains(r#"channel = "esp""#));
+
+    compile(&["--chip", "esp32s3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 created, 0 updated"));
+}
+
+#[test]
+fn compile_does_not_overwrite_existing_files() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("blink.yaml").write_str(BLINK).unwrap();
+    dir.child(".gitignore").write_str("*.log\n").unwrap();
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin", "--out-dir", "."])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("conflict  .gitignore"));
+    dir.child(".gitignore").assert("*.log\n");
+    dir.child("src/bin/main.rs").assert(predicate::path::exists());
+}
+
+#[test]
+fn dry_run_prints_diff_without_writing() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("blink.yaml").write_str(BLINK).unwrap();
+    let compile = |args: &[&str]| {
+        let mut cmd = espforge(&dir);
+        cmd.args(["compile", "blink.yaml", "-b", "builtin"])
+            .args(args);
+        cmd
+    };
+
+    compile(&["--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+++ b/src/bin/main.rs"));
+    dir.child("blink").assert(predicate::path::missing());
+
+    compile(&[]).assert().success();
+    dir.child("blink.yaml")
+        .write_str(&BLINK.replace("pin: 18", "pin: 5"))
+        .unwrap();
+
+    compile(&["--dry-run"]).assert().success().stdout(
+        predicate::str::contains("-    let mut red_led = LED::new(18);")
+            .and(predicate::str::contains(
+                "+    let mut red_led = LED::new(5);",
+            ))
+            .and(predicate::str::contains(
+                "(dry run) blink: 0 created, 1 updated",
+            )),
+    );
+    dir.child("blink/src/bin/main.rs")
+        .assert(predicate::str::contains("LED::new(18)"));
+}
+
+#[test]
+fn compile_into_output_directory() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = BLINK.replace(
+        "platform: esp32c3",
+        "platform: esp32c3\n  output: ../build/blink",
+    );
+    dir.child("configs/blink.yaml").write_str(&config).unwrap();
+
+    // `espforge.output` is relative to the configuration file
+    espforge(&dir)
+        .args(["compile", "configs/blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("build/blink/src/bin/main.rs")
+        .assert(predicate::path::exists());
+    dir.child("blink").assert(predicate::path::missing());
+
+    // `--out-dir` is relative to the current directory
+    espforge(&dir)
+        .args(["compile", "configs/blink.yaml", "-b", "builtin"])
+        .args(["--out-dir", "out/c3"])
+        .assert()
+        .success();
+    dir.child("out/c3/Cargo.toml")
+        .assert(predicate::str::contains(r#"name = "blink""#));
+}
+
+#[test]
+fn compile_with_local_modules() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = BLINK
+        .replace(
+            "platform: esp32c3",
+            "platform: esp32c3\n  module_paths: [../shared]",
+        )
+        .replace(
+            "direction: output }",
+            "direction: output }\n    gpio3: { pin: 19, direction: output }",
+        )
+        .replace(
+            "      gpio: $gpio2\n",
+            "      gpio: $gpio2\n  beeper:\n    using: Buzzer\n    with:\n      gpio: $gpio3\n",
+        )
+        .replace("    - $red_led.toggle:", "    - $red_led.toggle:\n    - $beeper.beep:");
+    dir.child("app/blink.yaml").write_str(&config).unwrap();
+
+    // A local LED next to the configuration replaces the built-in one
+    let led = include_str!("../components/led.ron");
+    dir.child("app/components/led.ron").write_str(led).unwrap();
+    dir.child("app/components/led.rs")
+        .write_str("pub struct LED; // local\n")
+        .unwrap();
+
+    // A new component from `espforge.module_paths`
+    let buzzer = led
+        .replace("\"LED\"", "\"Buzzer\"")
+        .replace("LED::new", "Buzzer::new")
+        .replace("\"toggle\"", "\"beep\"")
+        .replace("toggle();", "beep();");
+    dir.child("shared/components/buzzer.ron").write_str(&buzzer).unwrap();
+    dir.child("shared/components/buzzer.rs")
+        .write_str("pub struct Buzzer;\n")
+        .unwrap();
+
+    espforge(&dir)
+        .args(["compile", "app/blink.yaml", "-b", "builtin"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("local-module-override"));
+
+    let project = dir.child("blink");
+    project
+        .child("src/components/led.rs")
+        .assert("pub struct LED; // local\n");
+    project
+        .child("src/components/buzzer.rs")
+        .assert("pub struct Buzzer;\n");
+    project
+        .child("src/components/mod.rs")
+        .assert(predicate::str::contains("pub mod buzzer;"));
+    project
+        .child("src/bin/main.rs")
+        .assert(predicate::str::contains("Buzzer::new(19)").and(predicate::str::contains("beeper.beep();")));
+}
+
+#[test]
+fn install_pack_and_use_its_component() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    // The data directory `espforge()` points at
+    let data = dir.child("data");
+
+    let pack = dir.child("buzzers");
+    pack.child("pack.toml")
+        .write_str("name = \"buzzers\"\nversion = \"0.1.0\"\nespforge = \"^0.1\"\nchips = [\"esp32c3\"]\n")
+        .unwrap();
+    let buzzer = include_str!("../components/led.ron")
+        .replace("\"LED\"", "\"Buzzer\"")
+        .replace("LED::new", "Buzzer::new");
+    pack.child("components/buzzer.ron").write_str(&buzzer).unwrap();
+    pack.child("components/buzzer.rs")
+        .write_str("pub struct Buzzer;\n")
+        .unwrap();
+
+    // Packs are usually shared as archives
+    let archive = std::fs::File::create(dir.child("buzzers.tar.gz").path()).unwrap();
+    let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+        archive,
+        flate2::Compression::default(),
+    ));
+    tar.append_dir_all("buzzers", pack.path()).unwrap();
+    tar.into_inner().unwrap().finish().unwrap();
+
+    espforge(&dir)
+        .args(["pack", "add", "buzzers.tar.gz"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed pack 'buzzers' 0.1.0"));
+    data.child("packs/buzzers/components/buzzer.rs")
+        .assert(predicate::path::exists());
+
+    espforge(&dir)
+        .args(["pack", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("buzzers 0.1.0 (esp32c3)"));
+
+    // Installing the installed copy over itself must not delete it
+    espforge(&dir)
+        .args(["pack", "add", "data/packs/buzzers"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is the installed copy of pack 'buzzers'"));
+    data.child("packs/buzzers/components/buzzer.rs")
+        .assert(predicate::path::exists());
+
+    // Reinstalling from a directory replaces the pack in one step
+    espforge(&dir).args(["pack", "add", "buzzers"]).assert().success();
+    data.child("packs/buzzers/components/buzzer.rs")
+        .assert(predicate::path::exists());
+    data.child("packs/.buzzers.partial").assert(predicate::path::missing());
+
+    let config = BLINK.replace("using: LED", "using: Buzzer");
+    dir.child("blink.yaml").write_str(&config).unwrap();
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+
+    let project = dir.child("blink");
+    project
+        .child("src/components/buzzer.rs")
+        .assert("pub struct Buzzer;\n");
+    project
+        .child("src/bin/main.rs")
+        .assert(predicate::str::contains("Buzzer::new(18)"));
+
+    // A broken pack is skipped instead of failing every command
+    data.child("packs/broken/pack.toml")
+        .write_str("name = \"broken\"\n")
+        .unwrap();
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("skipping pack"));
+}
+
+#[test]
+fn compile_with_substitutions() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = format!(
+        "substitutions:\n  led_pin: 19\n  period: ${{env:BLINK_PERIOD}}\n{}",
+        BLINK
+            .replace("pin: 18", "pin: \"${led_pin}\"")
+            .replace("delay_millis: 500", "delay_millis: ${period}")
+    );
+    dir.child("blink.yaml").write_str(&config).unwrap();
+
+    espforge(&dir)
+        .env_remove("BLINK_PERIOD")
+        .args(["check", "blink.yaml"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("substitution-undefined"))
+        .stdout(predicate::str::contains("blink.yaml:3:11"));
+
+    espforge(&dir)
+        .env("BLINK_PERIOD", "250")
+        .args(["compile", "blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("blink/src/bin/main.rs").assert(
+        predicate::str::contains("LED::new(19)").and(predicate::str::contains("delay_millis(250)")),
+    );
+}
+
+#[test]
+fn compile_with_packages_and_include() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("common/board.yaml")
+        .write_str("espforge:\n  platform: esp32c3\nesp32:\n  gpio:\n    gpio2: { pin: 18, direction: output }\napp:\n  setup:\n    - log.info: \"board\"\n")
+        .unwrap();
+    dir.child("common/leds.yaml")
+        .write_str("red_led:\n  using: LED\n  with:\n    gpio: $gpio2\n")
+        .unwrap();
+    let config = "packages:\n  - ../common/board.yaml\nespforge:\n  name: blink\ncomponents: !include ../common/leds.yaml\napp:\n  setup:\n    - log.info: \"variant\"\n  loop:\n    - $red_led.toggle:\n";
+    dir.child("variants/blink.yaml").write_str(config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "variants/blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("blink/src/bin/main.rs").assert(
+        predicate::str::contains("LED::new(18)")
+            .and(predicate::str::is_match(r#"(?s)log.info\("board"\).*log.info\("variant"\)"#).unwrap()),
+    );
+
+    // A variant may not silently redefine what a package sets
+    let conflicting = config.replace("name: blink", "name: blink\n  platform: esp32s3");
+    dir.child("variants/blink.yaml").write_str(&conflicting).unwrap();
+    espforge(&dir)
+        .args(["check", "variants/blink.yaml"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Conflicting values for 'espforge.platform'"));
+}
+
+#[test]
+fn check_locates_findings_in_included_fragments() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    dir.child("common/board.yaml")
+        .write_str("espforge:\n  platform: esp32c3\nesp32:\n  gpio:\n    gpio2: { pin: 11, direction: output }\n")
+        .unwrap();
+    dir.child("common/leds.yaml")
+        .write_str("red_led:\n  using: LED\n  with:\n    gpio: $gpio9\n")
+        .unwrap();
+    let config = "packages:\n  - ../common/board.yaml\nespforge:\n  name: blink\ncomponents: !include ../common/leds.yaml\napp:\n  loop:\n    - $red_led.toggle:\n";
+    dir.child("variants/blink.yaml").write_str(config).unwrap();
+
+    espforge(&dir)
+        .args(["check", "variants/blink.yaml"])
+        .assert()
+        .failure()
+        .stdout(
+            predicate::str::contains("common/board.yaml:5:19")
+                .and(predicate::str::contains("common/leds.yaml:4:11"))
+                .and(predicate::str::contains("    gpio: $gpio9")),
+        );
+
+    espforge(&dir)
+        .args(["check", "variants/blink.yaml", "--format", "sarif"])
+        .assert()
+        .failure()
+        .stdout(
+            predicate::str::contains(r#""uri": "variants/../common/leds.yaml""#)
+                .and(predicate::str::contains(r#""uri": "variants/blink.yaml""#).not()),
+        );
+}
+
+#[test]
+fn compile_with_profile() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = "substitutions:\n  period: 1000\nespforge:\n  name: blink\n  platform: esp32c3\nesp32:\n  gpio:\n    gpio2: { pin: 18, direction: output }\ncomponents:\n  red_led:\n    using: LED\n    with:\n      gpio: $gpio2\napp:\n  loop:\n    - $red_led.toggle:\n    - delay.delay_millis: ${period}\nprofiles:\n  dev:\n    substitutions:\n      period: 100\n    app:\n      setup:\n        - log.info: \"dev build\"\n";
+    dir.child("blink.yaml").write_str(config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin", "--profile", "dev"])
+        .assert()
+        .success();
+    dir.child("blink/src/bin/main.rs").assert(
+        predicate::str::contains("log.info(\"dev build\")")
+            .and(predicate::str::contains("delay_millis(100)")),
+    );
+    dir.child("blink/Cargo.toml")
+        .assert(predicate::str::contains("options = [\"log\", \"esp-backtrace\", \"unstable-hal\", \"wokwi\"]\nprofile = \"dev\""));
+
+    // Without the profile the base configuration is used and the record removed
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("blink/src/bin/main.rs").assert(
+        predicate::str::contains("delay_millis(1000)").and(predicate::str::contains("dev build").not()),
+    );
+    dir.child("blink/Cargo.toml")
+        .assert(predicate::str::contains("profile =").not());
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin", "--profile", "prod"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile 'prod' not found; available profiles: dev"));
+}
+
+#[test]
+fn compile_with_board() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = "espforge:\n  name: blink\n  board: xiao-esp32s3\nesp32:\n  gpio:\n    led: { pin: LED_BUILTIN, direction: output }\ncomponents:\n  red_led:\n    using: LED\n    with:\n      gpio: $led\napp:\n  loop:\n    - $red_led.toggle:\n";
+    dir.child("blink.yaml").write_str(config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "blink.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("blink/src/bin/main.rs")
+        .assert(predicate::str::contains("LED::new(21)"));
+    dir.child("blink/.cargo/config.toml")
+        .assert(predicate::str::contains("xtensa-esp32s3-none-elf"));
+    dir.child("blink/diagram.json")
+        .assert(predicate::str::contains("\"type\": \"board-xiao-esp32-s3\""));
+
+    // Retargeting keeps the labels but is flagged
+    espforge(&dir)
+        .args(["check", "blink.yaml", "--chip", "esp32c3"])
+        .assert()
+        .stdout(predicate::str::contains("board-chip-mismatch"));
+}
+
+#[test]
+fn compile_with_gpio_settings() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let config = "espforge:\n  name: relay\n  platform: esp32c3\nesp32:\n  gpio:\n    coil: { pin: 4, direction: output, open_drain: true, drive_strength: 10, initial: high, inverted: true }\n    status: { pin: 5, direction: output }\ncomponents:\n  relay:\n    using: LED\n    with:\n      gpio: $coil\n  led:\n    using: LED\n    with:\n      gpio: $status\napp:\n  loop:\n    - $relay.toggle:\n";
+    dir.child("relay.yaml").write_str(config).unwrap();
+
+    espforge(&dir)
+        .args(["compile", "relay.yaml", "-b", "builtin"])
+        .assert()
+        .success();
+    dir.child("relay/src/bin/main.rs").assert(
+        predicate::str::contains("LED::with_settings(4, PinSettings { pull_up: false, pull_down: false, open_drain: true, drive_strength: 10, initial_high: true, inverted: true, input_output: false })")
+            .and(predicate::str::contains("LED::new(5)")),
+    );
+
+    let invalid = config.replace("drive_strength: 10", "drive_strength: 15");
+    dir.child("relay.yaml").write_str(&invalid).unwrap();
+    espforge(&dir)
+        .args(["check", "relay.yaml"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("gpio-drive-strength"));
+}